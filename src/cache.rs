@@ -0,0 +1,80 @@
+//! 扫描结果缓存
+//!
+//! 本模块将 [`Node`] 树以 JSON 保存到磁盘，之后可直接加载而无需重新遍历文件系统。
+//! 缓存头部记录扫描的根路径与时间，用于判断缓存是否过期。
+
+use crate::model::Node;
+use anyhow::{Context, bail};
+use serde::{Deserialize, Serialize};
+use std::io::{Read, Write};
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 缓存格式版本，结构不兼容时递增
+const CACHE_VERSION: u32 = 1;
+
+/// 缓存头部
+#[derive(Clone, Debug, Serialize, Deserialize)]
+pub struct CacheHeader {
+    /// 缓存格式版本
+    pub version: u32,
+    /// 扫描的根路径
+    pub root: PathBuf,
+    /// 保存时间（Unix 秒）
+    pub timestamp: u64,
+}
+
+impl CacheHeader {
+    /// 根目录在缓存保存之后是否被修改过（只检查根目录自身的修改时间）
+    pub fn is_stale(&self) -> bool {
+        std::fs::metadata(&self.root)
+            .and_then(|meta| meta.modified())
+            .ok()
+            .and_then(|mtime| mtime.duration_since(UNIX_EPOCH).ok())
+            .is_none_or(|mtime| mtime.as_secs() > self.timestamp)
+    }
+}
+
+#[derive(Serialize)]
+struct CacheRef<'a> {
+    header: CacheHeader,
+    tree: &'a Node,
+}
+
+#[derive(Deserialize)]
+struct Cache {
+    header: CacheHeader,
+    tree: Node,
+}
+
+impl Node {
+    /// 将文件树写入缓存
+    pub fn save_cache(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let cache = CacheRef {
+            header: CacheHeader {
+                version: CACHE_VERSION,
+                root: self.path().to_path_buf(),
+                timestamp,
+            },
+            tree: self,
+        };
+        serde_json::to_writer(writer, &cache).context("Cannot write cache")?;
+        Ok(())
+    }
+
+    /// 从缓存加载文件树，返回缓存头部与根节点
+    pub fn load_cache(reader: impl Read) -> anyhow::Result<(CacheHeader, Node)> {
+        let cache: Cache = serde_json::from_reader(reader).context("Invalid cache file")?;
+        if cache.header.version != CACHE_VERSION {
+            bail!(
+                "Unsupported cache version {} (expected {})",
+                cache.header.version,
+                CACHE_VERSION
+            );
+        }
+        Ok((cache.header, cache.tree))
+    }
+}
@@ -0,0 +1,94 @@
+//! 界面偏好设置
+//!
+//! 本模块读写保存排序、显示列等界面偏好的 TOML 文件，与扫描结果缓存无关。
+//! 文件位置见 [`Preferences::default_path`]，缺少的字段使用默认值。
+
+use crate::model::SortOrder;
+use crate::ui_state::DisplayColumn;
+use anyhow::Context;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::env;
+use std::path::{Path, PathBuf};
+
+/// 界面偏好
+#[derive(Clone, Debug, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Preferences {
+    /// 排序方式
+    pub sort: SortOrder,
+    /// 排序时目录是否优先于文件
+    pub group_dirs_first: bool,
+    /// 行尾显示的信息
+    pub column: DisplayColumn,
+    /// 是否使用颜色
+    pub color: bool,
+    /// 是否显示隐藏条目
+    pub show_hidden: bool,
+    /// 大小显示的小数位数（0–2）
+    pub size_precision: usize,
+    /// 按键绑定：动作名称 → 以空格分隔的按键，未列出的动作使用默认按键，见 [`KeyMap::with_overrides`](crate::KeyMap::with_overrides)
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub keys: BTreeMap<String, String>,
+}
+
+impl Default for Preferences {
+    fn default() -> Self {
+        Self {
+            sort: SortOrder::SizeDesc,
+            group_dirs_first: true,
+            column: DisplayColumn::Size,
+            color: true,
+            show_hidden: true,
+            size_precision: 1,
+            keys: BTreeMap::new(),
+        }
+    }
+}
+
+impl Preferences {
+    /// 默认的偏好文件路径
+    ///
+    /// 优先使用环境变量 `FSWHY_CONFIG`，其次为 `$XDG_CONFIG_HOME/fswhy/config.toml`、
+    /// `~/.config/fswhy/config.toml`（Windows 上为 `%APPDATA%\fswhy\config.toml`）；都无法确定时返回 `None`。
+    pub fn default_path() -> Option<PathBuf> {
+        if let Some(path) = env::var_os("FSWHY_CONFIG").filter(|v| !v.is_empty()) {
+            return Some(PathBuf::from(path));
+        }
+        let non_empty = |name| {
+            env::var_os(name)
+                .filter(|v| !v.is_empty())
+                .map(PathBuf::from)
+        };
+        let dir = if cfg!(windows) {
+            non_empty("APPDATA")?
+        } else {
+            non_empty("XDG_CONFIG_HOME")
+                .or_else(|| non_empty("HOME").map(|home| home.join(".config")))?
+        };
+        Some(dir.join("fswhy").join("config.toml"))
+    }
+
+    /// 从文件加载偏好，文件不存在时返回默认值
+    ///
+    /// # 错误
+    /// 文件存在但无法读取或格式不正确时返回错误。
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let text = match std::fs::read_to_string(path) {
+            Ok(text) => text,
+            Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(Self::default()),
+            Err(e) => return Err(e).with_context(|| format!("Cannot read {}", path.display())),
+        };
+        toml::from_str(&text).with_context(|| format!("Invalid preferences in {}", path.display()))
+    }
+
+    /// 保存偏好到文件，必要时创建所在目录
+    pub fn save(&self, path: &Path) -> anyhow::Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)
+                .with_context(|| format!("Cannot create {}", parent.display()))?;
+        }
+        std::fs::write(path, toml::to_string(self)?)
+            .with_context(|| format!("Cannot write {}", path.display()))
+    }
+}
@@ -0,0 +1,88 @@
+//! 库接口的错误类型
+//!
+//! 扫描相关的公开函数（如 [`Node::scan`](crate::model::Node::scan)、[`App::new`](crate::App::new)）返回 [`FswhyError`]，
+//! 调用方可以按失败原因分别处理。它实现了 [`std::error::Error`]，也可以直接用 `?` 转换为 `anyhow::Error`。
+
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+
+/// 扫描失败的原因
+#[derive(Debug)]
+#[non_exhaustive]
+pub enum FswhyError {
+    /// 没有指定要扫描的路径
+    NoPath,
+    /// 路径不存在
+    NotFound(PathBuf),
+    /// 没有访问路径的权限
+    PermissionDenied(PathBuf),
+    /// 需要目录的位置不是目录（如普通文件）
+    NotADirectory(PathBuf),
+    /// 访问路径时发生的其他 IO 错误
+    Io { path: PathBuf, source: io::Error },
+    /// 扫描线程意外终止
+    ThreadPanicked,
+}
+
+impl FswhyError {
+    /// 按 IO 错误的类型归类访问 `path` 时发生的错误
+    pub fn from_io(path: impl Into<PathBuf>, error: io::Error) -> Self {
+        let path = path.into();
+        match error.kind() {
+            io::ErrorKind::NotFound => Self::NotFound(path),
+            io::ErrorKind::PermissionDenied => Self::PermissionDenied(path),
+            io::ErrorKind::NotADirectory => Self::NotADirectory(path),
+            _ => Self::Io {
+                path,
+                source: error,
+            },
+        }
+    }
+
+    /// 出错的路径，与具体路径无关的错误为 `None`
+    pub fn path(&self) -> Option<&Path> {
+        match self {
+            Self::NotFound(path)
+            | Self::PermissionDenied(path)
+            | Self::NotADirectory(path)
+            | Self::Io { path, .. } => Some(path),
+            Self::NoPath | Self::ThreadPanicked => None,
+        }
+    }
+
+    /// 对应的 IO 错误类型，与 IO 无关的错误记为 [`Other`](io::ErrorKind::Other)
+    pub fn io_kind(&self) -> io::ErrorKind {
+        match self {
+            Self::NotFound(_) => io::ErrorKind::NotFound,
+            Self::PermissionDenied(_) => io::ErrorKind::PermissionDenied,
+            Self::NotADirectory(_) => io::ErrorKind::NotADirectory,
+            Self::Io { source, .. } => source.kind(),
+            Self::NoPath | Self::ThreadPanicked => io::ErrorKind::Other,
+        }
+    }
+}
+
+impl fmt::Display for FswhyError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Self::NoPath => write!(f, "No path to scan"),
+            Self::NotFound(path) => write!(f, "Cannot access {}: not found", path.display()),
+            Self::PermissionDenied(path) => {
+                write!(f, "Cannot access {}: permission denied", path.display())
+            }
+            Self::NotADirectory(path) => write!(f, "{} is not a directory", path.display()),
+            Self::Io { path, source } => write!(f, "Cannot access {}: {source}", path.display()),
+            Self::ThreadPanicked => write!(f, "Scan thread panicked"),
+        }
+    }
+}
+
+impl std::error::Error for FswhyError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Self::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
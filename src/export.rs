@@ -0,0 +1,157 @@
+//! 文件树导出
+//!
+//! 本模块将 [`Node`] 树转换为可供其他工具读取的格式，大小均保留为原始字节数。
+
+use crate::model::{Node, NodeKind};
+use serde_json::{Value, json};
+use std::borrow::Cow;
+use std::io::Write;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+impl Node {
+    /// 转换为 JSON：每个节点包含 `path`、`relative_path`（相对于自身，见 [`Node::relative_path`]）、
+    /// `size`、`kind`，目录额外包含嵌套的 `children` 数组
+    pub fn to_json(&self) -> Value {
+        self.to_json_under(self.path())
+    }
+
+    /// 转换为 JSON，`relative_path` 相对于 `root`
+    fn to_json_under(&self, root: &Path) -> Value {
+        let path = self.path().to_string_lossy();
+        let relative_path = self.relative_path(root).to_string_lossy();
+        match self.kind() {
+            NodeKind::Directory(prop) => json!({
+                "path": path,
+                "relative_path": relative_path,
+                "size": self.size(),
+                "kind": "directory",
+                "children": prop
+                    .children()
+                    .iter()
+                    .map(|child| child.to_json_under(root))
+                    .collect::<Vec<_>>(),
+            }),
+            NodeKind::File => json!({
+                "path": path,
+                "relative_path": relative_path,
+                "size": self.size(),
+                "kind": "file",
+            }),
+            NodeKind::Symlink => json!({
+                "path": path,
+                "relative_path": relative_path,
+                "size": self.size(),
+                "kind": "symlink",
+            }),
+            NodeKind::Omitted(entries) => json!({
+                "path": path,
+                "relative_path": relative_path,
+                "size": self.size(),
+                "kind": "omitted",
+                "count": entries.count(),
+            }),
+        }
+    }
+
+    /// 以 ncdu 导出格式（v1.x）写出文件树，可通过 `ncdu -f` 导入
+    ///
+    /// 文件的 `asize`/`dsize` 均为扫描时按 [`SizeMode`](crate::model::SizeMode) 统计的大小；
+    /// ncdu 会自行累加目录总大小，因此目录条目不携带大小，只有未保留子节点的目录以其总大小作为自身大小。
+    pub fn export_ncdu(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map_or(0, |d| d.as_secs());
+        let metadata = json!({
+            "progname": env!("CARGO_PKG_NAME"),
+            "progver": env!("CARGO_PKG_VERSION"),
+            "timestamp": timestamp,
+        });
+
+        write!(writer, "[1,0,")?;
+        serde_json::to_writer(&mut *writer, &metadata)?;
+        write!(writer, ",")?;
+        self.write_ncdu_entry(writer, true)?;
+        writeln!(writer, "]")?;
+        Ok(())
+    }
+
+    /// 以 CSV 写出每个节点一行：`path,relative_path,size,kind,depth`，首行为表头
+    ///
+    /// `relative_path` 相对于自身（见 [`Node::relative_path`]），大小为原始字节数，`kind` 为 `directory`/`file`/`symlink`/`omitted`，根节点深度为 0。
+    /// 含逗号、引号或换行的路径按 RFC 4180 加引号转义。
+    pub fn write_csv(&self, writer: &mut impl Write) -> anyhow::Result<()> {
+        writeln!(writer, "path,relative_path,size,kind,depth")?;
+        for (node, depth) in self.iter() {
+            let kind = match node.kind() {
+                NodeKind::Directory(_) => "directory",
+                NodeKind::File => "file",
+                NodeKind::Symlink => "symlink",
+                NodeKind::Omitted(_) => "omitted",
+            };
+            writeln!(
+                writer,
+                "{},{},{},{},{}",
+                csv_field(&node.path().to_string_lossy()),
+                csv_field(&node.relative_path(self.path()).to_string_lossy()),
+                node.size(),
+                kind,
+                depth
+            )?;
+        }
+        Ok(())
+    }
+
+    /// 写出单个 ncdu 条目：目录为 `[信息, 子条目...]` 数组，其余为信息对象
+    fn write_ncdu_entry(&self, writer: &mut impl Write, is_root: bool) -> anyhow::Result<()> {
+        // 根条目使用完整路径，其余使用文件名
+        let name = match self.path().file_name() {
+            Some(name) if !is_root => name.to_string_lossy(),
+            _ => self.path().to_string_lossy(),
+        };
+
+        match self.kind() {
+            NodeKind::Directory(prop) => {
+                // ncdu 以 `excluded: "othfs"` 表示其他文件系统
+                let info = if prop.is_other_filesystem() {
+                    json!({ "name": name, "excluded": "othfs" })
+                } else if prop.is_truncated() {
+                    json!({ "name": name, "asize": self.size(), "dsize": self.size() })
+                } else {
+                    json!({ "name": name })
+                };
+                write!(writer, "[")?;
+                serde_json::to_writer(&mut *writer, &info)?;
+                for child in prop.children() {
+                    write!(writer, ",")?;
+                    child.write_ncdu_entry(writer, false)?;
+                }
+                write!(writer, "]")?;
+            }
+            NodeKind::File => {
+                let info = json!({ "name": name, "asize": self.size(), "dsize": self.size() });
+                serde_json::to_writer(&mut *writer, &info)?;
+            }
+            // 合并的条目不对应实际文件，与符号链接一样标记为非普通文件
+            NodeKind::Symlink | NodeKind::Omitted(_) => {
+                let info = json!({
+                    "name": name,
+                    "asize": self.size(),
+                    "dsize": self.size(),
+                    "notreg": true,
+                });
+                serde_json::to_writer(&mut *writer, &info)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// 转义 CSV 字段：含逗号、引号或换行时整体加引号，内部引号加倍
+fn csv_field(value: &str) -> Cow<'_, str> {
+    if value.contains([',', '"', '\n', '\r']) {
+        Cow::Owned(format!("\"{}\"", value.replace('"', "\"\"")))
+    } else {
+        Cow::Borrowed(value)
+    }
+}
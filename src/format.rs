@@ -0,0 +1,180 @@
+//! 人类可读的数值与名称格式化
+//!
+//! 非 UTF-8 的文件名统一按 [`Path::to_string_lossy`] 显示，无效字节替换为 `U+FFFD`（�）；
+//! 文件树本身始终保存原始路径，删除、打开等操作不受显示方式影响。
+
+use std::borrow::Cow;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// 大小单位的进制
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SizeBase {
+    /// 1024 进制（KiB、MiB…）
+    #[default]
+    Binary,
+    /// 1000 进制（KB、MB…），即 SI 单位
+    Decimal,
+}
+
+impl SizeBase {
+    /// 每级单位的倍数
+    fn factor(self) -> f64 {
+        match self {
+            SizeBase::Binary => 1024.0,
+            SizeBase::Decimal => 1000.0,
+        }
+    }
+
+    /// B 以上各级单位的后缀
+    fn suffixes(self) -> [&'static str; 4] {
+        match self {
+            SizeBase::Binary => ["KiB", "MiB", "GiB", "TiB"],
+            SizeBase::Decimal => ["KB", "MB", "GB", "TB"],
+        }
+    }
+}
+
+/// 小数位数的上限
+pub const MAX_SIZE_PRECISION: usize = 2;
+
+/// 大小的显示方式：单位的进制与小数位数
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct SizeFormat {
+    pub base: SizeBase,
+    /// 小数位数，超过 [`MAX_SIZE_PRECISION`] 时按上限处理；以 `B` 为单位时总是整数
+    pub precision: usize,
+}
+
+impl Default for SizeFormat {
+    fn default() -> Self {
+        Self {
+            base: SizeBase::Binary,
+            precision: 1,
+        }
+    }
+}
+
+/// 格式化文件大小，如 `512 B`、`1.5 KiB`、`5.0 GB`（小数位数为 0 时如 `2 KiB`）
+///
+/// 按小数位数舍入后达到下一级单位时进位，如 `1023.96 KiB` 显示为 `1.0 MiB` 而不是 `1024.0 KiB`。
+pub fn format_size(bytes: u64, format: SizeFormat) -> String {
+    let factor = format.base.factor();
+    if (bytes as f64) < factor {
+        return format!("{} B", bytes);
+    }
+
+    let precision = format.precision.min(MAX_SIZE_PRECISION);
+    let scale = 10f64.powi(precision as i32);
+    let mut value = bytes as f64 / factor;
+    let mut unit = 0;
+    let suffixes = format.base.suffixes();
+    while (value * scale).round() / scale >= factor && unit + 1 < suffixes.len() {
+        value /= factor;
+        unit += 1;
+    }
+    format!("{:.*} {}", precision, value, suffixes[unit])
+}
+
+/// 格式化大小的变化，带正负号，如 `+1.5 KiB`、`-512 B`；没有变化时为 `0 B`
+pub fn format_delta(delta: i64, format: SizeFormat) -> String {
+    let sign = match delta.signum() {
+        1 => "+",
+        -1 => "-",
+        _ => "",
+    };
+    format!("{sign}{}", format_size(delta.unsigned_abs(), format))
+}
+
+/// 格式化时间为 UTC 的 `YYYY-MM-DD HH:MM`，早于 1970 年的时间显示为 `-`
+pub fn format_time(time: SystemTime) -> String {
+    let Ok(elapsed) = time.duration_since(UNIX_EPOCH) else {
+        return "-".to_string();
+    };
+    let secs = elapsed.as_secs();
+    let (days, rem) = (secs / 86400, secs % 86400);
+    let (year, month, day) = civil_from_days(days as i64);
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02}",
+        year,
+        month,
+        day,
+        rem / 3600,
+        rem % 3600 / 60
+    )
+}
+
+/// 将 1970-01-01 起的天数转换为公历年月日（Howard Hinnant 的算法）
+fn civil_from_days(days: i64) -> (i64, u32, u32) {
+    let z = days + 719_468;
+    let era = z.div_euclid(146_097);
+    let doe = z.rem_euclid(146_097);
+    let yoe = (doe - doe / 1460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = (doy - (153 * mp + 2) / 5 + 1) as u32;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 } as u32;
+    let year = yoe + era * 400 + i64::from(month <= 2);
+    (year, month, day)
+}
+
+/// 解析带单位的大小，如 `4096`、`10M`、`1.5GiB`、`500KB`
+///
+/// 单独的 `K`/`M`/`G`/`T` 与 `KiB` 等按 1024 进制，`KB` 等按 1000 进制，单位不区分大小写。
+pub fn parse_size(text: &str) -> anyhow::Result<u64> {
+    let text = text.trim();
+    let split = text
+        .find(|c: char| !(c.is_ascii_digit() || c == '.'))
+        .unwrap_or(text.len());
+    let (number, unit) = text.split_at(split);
+    let value: f64 = number
+        .parse()
+        .map_err(|_| anyhow::anyhow!("Invalid size: {text}"))?;
+
+    let unit = unit.trim().to_ascii_lowercase();
+    let (prefix, base) = match unit.strip_suffix("ib") {
+        Some(prefix) => (prefix, SizeBase::Binary),
+        None => match unit.strip_suffix('b') {
+            Some(prefix) if !prefix.is_empty() => (prefix, SizeBase::Decimal),
+            Some(prefix) => (prefix, SizeBase::Binary),
+            None => (unit.as_str(), SizeBase::Binary),
+        },
+    };
+    let exponent = match prefix {
+        "" => 0,
+        "k" => 1,
+        "m" => 2,
+        "g" => 3,
+        "t" => 4,
+        _ => anyhow::bail!("Unknown size unit in {text}"),
+    };
+    Ok((value * base.factor().powi(exponent)).round() as u64)
+}
+
+/// 路径最后一段的显示名称，没有文件名的路径（如 `.`、`/`）显示完整路径
+pub fn display_name(path: &Path) -> Cow<'_, str> {
+    path.file_name()
+        .unwrap_or(path.as_os_str())
+        .to_string_lossy()
+}
+
+/// 将超过 `max_chars` 个字符的文本截去中间部分，保留开头与结尾，如 `verylong…name.txt`
+///
+/// 按字符而非显示宽度计数；`max_chars` 为 0 时返回空字符串。
+pub fn truncate_middle(text: &str, max_chars: usize) -> Cow<'_, str> {
+    let len = text.chars().count();
+    if len <= max_chars {
+        return Cow::Borrowed(text);
+    }
+    if max_chars == 0 {
+        return Cow::Borrowed("");
+    }
+    // 省略号占一个字符，结尾多保留一些以显示扩展名
+    let keep = max_chars - 1;
+    let head = keep / 2;
+    let tail = keep - head;
+    let mut result: String = text.chars().take(head).collect();
+    result.push('…');
+    result.extend(text.chars().skip(len - tail));
+    Cow::Owned(result)
+}
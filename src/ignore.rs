@@ -0,0 +1,261 @@
+//! 忽略规则匹配
+//!
+//! 本模块实现了 `.gitignore` 语法的子集，供扫描时跳过匹配的路径。每个 `.gitignore` 文件的模式都相对于其所在目录，
+//! 嵌套目录中的规则会覆盖上层规则，与 git 的行为一致。
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+
+/// 单条忽略模式
+#[derive(Debug)]
+struct IgnorePattern {
+    glob: Vec<char>,
+    negated: bool,  // `!` 开头，重新包含
+    dir_only: bool, // `/` 结尾，只匹配目录
+    anchored: bool, // 含 `/`，相对于规则所在目录匹配完整路径
+}
+
+impl IgnorePattern {
+    /// 解析一行规则，空行与注释返回 `None`
+    fn parse(line: &str) -> Option<Self> {
+        let line = line.trim_end();
+        if line.is_empty() || line.starts_with('#') {
+            return None;
+        }
+
+        let (negated, line) = match line.strip_prefix('!') {
+            Some(rest) => (true, rest),
+            None => (false, line.strip_prefix('\\').unwrap_or(line)),
+        };
+        let (dir_only, line) = match line.strip_suffix('/') {
+            Some(rest) => (true, rest),
+            None => (false, line),
+        };
+        if line.is_empty() {
+            return None;
+        }
+
+        let anchored = line.contains('/');
+        let line = line.strip_prefix('/').unwrap_or(line);
+        Some(Self {
+            glob: line.chars().collect(),
+            negated,
+            dir_only,
+            anchored,
+        })
+    }
+
+    /// 匹配相对于规则目录的路径（以 `/` 分隔）
+    fn matches(&self, relative: &str, is_dir: bool) -> bool {
+        if self.dir_only && !is_dir {
+            return false;
+        }
+        let target = if self.anchored {
+            relative
+        } else {
+            relative.rsplit('/').next().unwrap_or(relative)
+        };
+        let text: Vec<char> = target.chars().collect();
+        glob_match(&self.glob, &text)
+    }
+}
+
+/// 一组共享同一基准目录的规则（一个 `.gitignore` 文件或命令行传入的模式）
+#[derive(Debug)]
+struct IgnoreRules {
+    /// 被匹配路径需以此开头
+    base: PathBuf,
+    /// 规则所在目录到 `base` 的相对路径，规则位于 `base` 之上时非空
+    prefix: String,
+    patterns: Vec<IgnorePattern>,
+}
+
+impl IgnoreRules {
+    fn parse<'a>(base: PathBuf, prefix: String, lines: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            base,
+            prefix,
+            patterns: lines.into_iter().filter_map(IgnorePattern::parse).collect(),
+        }
+    }
+
+    /// 最后一条匹配的规则决定结果，无匹配返回 `None`
+    fn decide(&self, path: &Path, is_dir: bool) -> Option<bool> {
+        let relative = path.strip_prefix(&self.base).ok()?;
+        let relative = format!("{}{}", self.prefix, to_slash(relative));
+        self.patterns
+            .iter()
+            .rev()
+            .find(|p| p.matches(&relative, is_dir))
+            .map(|p| !p.negated)
+    }
+}
+
+/// 逐层叠加的忽略规则栈，可在线程间共享
+#[derive(Debug)]
+pub(crate) struct IgnoreStack {
+    parent: Option<Arc<IgnoreStack>>,
+    rules: IgnoreRules,
+}
+
+impl IgnoreStack {
+    /// 以 `base` 为基准目录压入一组模式
+    pub(crate) fn push_patterns<S: AsRef<str>>(
+        parent: Option<Arc<IgnoreStack>>,
+        base: &Path,
+        patterns: &[S],
+    ) -> Option<Arc<IgnoreStack>> {
+        let rules = IgnoreRules::parse(
+            base.to_path_buf(),
+            String::new(),
+            patterns.iter().map(AsRef::as_ref),
+        );
+        Self::push(parent, rules)
+    }
+
+    /// 若 `dir` 中存在 `.gitignore`，压入其规则
+    pub(crate) fn push_gitignore(
+        parent: Option<Arc<IgnoreStack>>,
+        dir: &Path,
+    ) -> Option<Arc<IgnoreStack>> {
+        match fs::read_to_string(dir.join(".gitignore")) {
+            Ok(text) => Self::push(
+                parent,
+                IgnoreRules::parse(dir.to_path_buf(), String::new(), text.lines()),
+            ),
+            Err(_) => parent,
+        }
+    }
+
+    /// 压入扫描根目录之上、所在 git 仓库内的 `.gitignore`
+    ///
+    /// 从根目录向上查找包含 `.git` 的目录；若根目录不在仓库中则不加载任何上层规则。
+    pub(crate) fn push_ancestor_gitignores(
+        parent: Option<Arc<IgnoreStack>>,
+        root: &Path,
+    ) -> Option<Arc<IgnoreStack>> {
+        let Ok(canonical) = root.canonicalize() else {
+            return parent;
+        };
+        let Some(repo) = canonical.ancestors().find(|dir| dir.join(".git").exists()) else {
+            return parent;
+        };
+
+        // 从仓库根向下依次压入，使更深的规则优先；扫描根目录本身的规则由扫描过程加载
+        let mut ancestors: Vec<&Path> = canonical
+            .ancestors()
+            .skip(1)
+            .take_while(|dir| dir.starts_with(repo))
+            .collect();
+        ancestors.reverse();
+        ancestors.into_iter().fold(parent, |stack, dir| {
+            let Ok(text) = fs::read_to_string(dir.join(".gitignore")) else {
+                return stack;
+            };
+            // 被匹配路径仍以原始根路径开头，通过前缀补齐到规则目录的相对路径
+            let prefix = canonical
+                .strip_prefix(dir)
+                .map(to_slash)
+                .unwrap_or_default();
+            let rules = IgnoreRules::parse(root.to_path_buf(), format!("{prefix}/"), text.lines());
+            Self::push(stack, rules)
+        })
+    }
+
+    fn push(parent: Option<Arc<IgnoreStack>>, rules: IgnoreRules) -> Option<Arc<IgnoreStack>> {
+        if rules.patterns.is_empty() {
+            return parent;
+        }
+        Some(Arc::new(IgnoreStack { parent, rules }))
+    }
+
+    /// 判断路径是否被忽略，越深层的规则优先
+    pub(crate) fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        let mut frame = Some(self);
+        while let Some(stack) = frame {
+            if let Some(ignored) = stack.rules.decide(path, is_dir) {
+                return ignored;
+            }
+            frame = stack.parent.as_deref();
+        }
+        false
+    }
+}
+
+/// 转换为以 `/` 分隔的路径字符串
+fn to_slash(path: &Path) -> String {
+    path.to_string_lossy().replace('\\', "/")
+}
+
+/// 通配符匹配
+///
+/// 支持 `*`（不跨越 `/`）、`**`（跨越任意层目录）、`?`、`[...]` 字符类与 `\` 转义。
+pub(crate) fn glob_match(pattern: &[char], text: &[char]) -> bool {
+    match pattern.first() {
+        None => text.is_empty(),
+        Some('*') if pattern.get(1) == Some(&'*') => {
+            let rest = &pattern[2..];
+            // `**/` 可以匹配零层目录
+            if rest.first() == Some(&'/') && glob_match(&rest[1..], text) {
+                return true;
+            }
+            (0..=text.len()).any(|i| glob_match(rest, &text[i..]))
+        }
+        Some('*') => {
+            let rest = &pattern[1..];
+            for i in 0..=text.len() {
+                if glob_match(rest, &text[i..]) {
+                    return true;
+                }
+                if text.get(i) == Some(&'/') {
+                    break;
+                }
+            }
+            false
+        }
+        Some('?') => {
+            matches!(text.first(), Some(c) if *c != '/') && glob_match(&pattern[1..], &text[1..])
+        }
+        Some('[') => match match_class(&pattern[1..], text.first().copied()) {
+            Some((matched, consumed)) => {
+                matched && glob_match(&pattern[1 + consumed..], &text[1..])
+            }
+            // 未闭合的 `[` 按字面量处理
+            None => text.first() == Some(&'[') && glob_match(&pattern[1..], &text[1..]),
+        },
+        Some('\\') if pattern.len() > 1 => {
+            text.first() == Some(&pattern[1]) && glob_match(&pattern[2..], &text[1..])
+        }
+        Some(c) => text.first() == Some(c) && glob_match(&pattern[1..], &text[1..]),
+    }
+}
+
+/// 匹配字符类，返回（是否匹配，消耗的模式字符数）；字符类未闭合时返回 `None`
+fn match_class(pattern: &[char], ch: Option<char>) -> Option<(bool, usize)> {
+    let mut i = 0;
+    let negated = matches!(pattern.first(), Some('!' | '^'));
+    if negated {
+        i += 1;
+    }
+
+    let mut matched = false;
+    let mut first = true;
+    while i < pattern.len() {
+        let c = pattern[i];
+        if c == ']' && !first {
+            let matched = matched != negated && ch.is_some_and(|ch| ch != '/');
+            return Some((matched, i + 1));
+        }
+        first = false;
+        if pattern.get(i + 1) == Some(&'-') && pattern.get(i + 2).is_some_and(|&e| e != ']') {
+            let end = pattern[i + 2];
+            matched |= ch.is_some_and(|ch| c <= ch && ch <= end);
+            i += 3;
+        } else {
+            matched |= ch == Some(c);
+            i += 1;
+        }
+    }
+    None
+}
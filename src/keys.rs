@@ -0,0 +1,201 @@
+//! 按键绑定
+//!
+//! 普通模式下按键到 [`Action`] 的映射由 [`KeyMap`] 保存：内置的默认绑定可以被偏好文件 `[keys]` 表中的设置覆盖，
+//! 如 `quit = "x"`、`toggle = "enter"`。数字键（目录编号）、`Backspace` 与 `Ctrl+C` 固定不可重新绑定。
+
+use crate::ui_state::Action;
+use anyhow::bail;
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::collections::{BTreeMap, HashMap};
+
+/// 可绑定的动作：配置中的名称、动作与默认按键（第一个按键显示在帮助栏中）
+const DEFAULT_BINDINGS: &[(&str, Action, &[&str])] = &[
+    ("move_up", Action::MoveUp, &["k", "K", "up"]),
+    ("move_down", Action::MoveDown, &["j", "J", "down"]),
+    ("page_up", Action::PageUp, &["pageup"]),
+    ("page_down", Action::PageDown, &["pagedown"]),
+    ("expand", Action::Expand, &["l", "L", "right"]),
+    ("collapse", Action::Collapse, &["h", "H", "left"]),
+    ("parent", Action::JumpToParent, &["p", "P"]),
+    ("toggle", Action::Enter, &["enter"]),
+    ("toggle_cursor", Action::ToggleAtCursor, &["t", "T"]),
+    ("expand_all", Action::ExpandAll, &["E"]),
+    ("collapse_all", Action::CollapseAll, &["C"]),
+    ("focus", Action::ToggleFocus, &["z", "Z"]),
+    ("sort", Action::ToggleSort, &["s", "S"]),
+    ("sort_directory", Action::SortDirectory, &["a", "A"]),
+    ("counts", Action::ToggleFileCount, &["#"]),
+    ("column", Action::CycleColumn, &["v", "V"]),
+    ("relative", Action::ToggleRelative, &["%"]),
+    ("hidden", Action::ToggleHidden, &["."]),
+    ("dirs_only", Action::ToggleDirsOnly, &["f", "F"]),
+    ("breakdown", Action::ToggleBreakdown, &["b", "B"]),
+    ("min_size", Action::ToggleMinSize, &["-"]),
+    ("search", Action::StartSearch, &["/"]),
+    ("jump", Action::StartJump, &["g"]),
+    ("clear_search", Action::ClearSearch, &["esc"]),
+    ("rescan", Action::Rescan, &["r", "R"]),
+    ("open", Action::Open, &["o", "O"]),
+    ("copy_path", Action::Yank, &["y", "Y"]),
+    // Space 原先用于展开/折叠，现在用于标记；展开/折叠使用 Enter 或 `t`
+    ("mark", Action::ToggleMark, &["space"]),
+    ("move", Action::StartMove, &["m", "M"]),
+    // 回收站使用 `x` 而不是 `t`，`t` 保留给切换光标处目录
+    ("trash", Action::Trash, &["x", "X"]),
+    ("delete", Action::Delete, &["d"]),
+    ("delete_marked", Action::DeleteMarked, &["D"]),
+    ("quit", Action::Quit, &["q", "Q"]),
+];
+
+/// 有名称的按键，名称不区分大小写；第二项为帮助栏中显示的名称
+const NAMED_KEYS: &[(&str, KeyCode, &str)] = &[
+    ("enter", KeyCode::Enter, "Enter"),
+    ("esc", KeyCode::Esc, "Esc"),
+    ("escape", KeyCode::Esc, "Esc"),
+    ("space", KeyCode::Char(' '), "Space"),
+    ("tab", KeyCode::Tab, "Tab"),
+    ("up", KeyCode::Up, "Up"),
+    ("down", KeyCode::Down, "Down"),
+    ("left", KeyCode::Left, "Left"),
+    ("right", KeyCode::Right, "Right"),
+    ("pageup", KeyCode::PageUp, "PgUp"),
+    ("pagedown", KeyCode::PageDown, "PgDn"),
+    ("home", KeyCode::Home, "Home"),
+    ("end", KeyCode::End, "End"),
+    ("delete", KeyCode::Delete, "Del"),
+    ("backspace", KeyCode::Backspace, "Backspace"),
+];
+
+/// 普通模式下的按键绑定
+#[derive(Clone, Debug)]
+pub struct KeyMap {
+    bindings: HashMap<KeyEvent, Action>,
+    /// 各动作在帮助栏中显示的按键
+    labels: HashMap<Action, String>,
+}
+
+impl Default for KeyMap {
+    fn default() -> Self {
+        Self::with_overrides(&BTreeMap::new()).expect("default key bindings are valid")
+    }
+}
+
+impl KeyMap {
+    /// 以 `overrides`（动作名称 → 以空格分隔的按键）覆盖默认绑定
+    ///
+    /// 覆盖某个动作时，该动作的默认按键全部失效。按键写作单个字符（区分大小写，如 `x`、`X`）、
+    /// 按键名称（如 `enter`、`space`、`pageup`），可加 `ctrl+` 前缀。
+    ///
+    /// # 错误
+    /// 动作名称未知、按键无法解析、按键固定不可绑定，或同一按键绑定到多个动作时返回错误。
+    pub fn with_overrides(overrides: &BTreeMap<String, String>) -> anyhow::Result<Self> {
+        if let Some(name) = overrides
+            .keys()
+            .find(|name| !DEFAULT_BINDINGS.iter().any(|(n, _, _)| n == name))
+        {
+            let names: Vec<&str> = DEFAULT_BINDINGS.iter().map(|(n, _, _)| *n).collect();
+            bail!(
+                "Unknown action `{name}` in [keys]; expected one of: {}",
+                names.join(", ")
+            );
+        }
+
+        let mut bindings: HashMap<KeyEvent, (&str, Action)> = HashMap::new();
+        let mut labels = HashMap::new();
+        for &(name, action, defaults) in DEFAULT_BINDINGS {
+            let keys: Vec<&str> = match overrides.get(name) {
+                Some(value) => value.split_whitespace().collect(),
+                None => defaults.to_vec(),
+            };
+            if keys.is_empty() {
+                bail!("No key given for `{name}` in [keys]");
+            }
+            for text in keys {
+                let key = parse_key(text)?;
+                if is_reserved(&key) {
+                    bail!("Key `{text}` for `{name}` is reserved and cannot be rebound");
+                }
+                if let Some((other, _)) = bindings.insert(key, (name, action))
+                    && other != name
+                {
+                    bail!("Key `{text}` is bound to both `{other}` and `{name}`");
+                }
+                labels.entry(action).or_insert_with(|| key_label(&key));
+            }
+        }
+        Ok(Self {
+            bindings: bindings
+                .into_iter()
+                .map(|(key, (_, action))| (key, action))
+                .collect(),
+            labels,
+        })
+    }
+
+    /// 按键绑定的动作
+    pub fn action(&self, key: &KeyEvent) -> Option<Action> {
+        self.bindings.get(&normalize(key)).copied()
+    }
+
+    /// 动作在帮助栏中显示的按键，未绑定时为 `?`
+    pub fn label(&self, action: Action) -> &str {
+        self.labels.get(&action).map_or("?", String::as_str)
+    }
+}
+
+/// 解析按键，如 `x`、`X`、`enter`、`ctrl+f`
+fn parse_key(text: &str) -> anyhow::Result<KeyEvent> {
+    let (modifiers, rest) = match text.get(..5) {
+        Some(prefix) if prefix.eq_ignore_ascii_case("ctrl+") && text.len() > 5 => {
+            (KeyModifiers::CONTROL, &text[5..])
+        }
+        _ => (KeyModifiers::NONE, text),
+    };
+    let mut chars = rest.chars();
+    let code = match (chars.next(), chars.next()) {
+        (Some(ch), None) => KeyCode::Char(ch),
+        _ => match NAMED_KEYS
+            .iter()
+            .find(|(name, _, _)| name.eq_ignore_ascii_case(rest))
+        {
+            Some(&(_, code, _)) => code,
+            None => bail!("Unknown key `{text}` in [keys]"),
+        },
+    };
+    Ok(KeyEvent::new(code, modifiers))
+}
+
+/// 固定不可重新绑定的按键：数字、`Backspace` 与 `Ctrl+C`
+fn is_reserved(key: &KeyEvent) -> bool {
+    match key.code {
+        KeyCode::Char(ch) if ch.is_ascii_digit() => true,
+        KeyCode::Char('c' | 'C') => key.modifiers.contains(KeyModifiers::CONTROL),
+        KeyCode::Backspace => true,
+        _ => false,
+    }
+}
+
+/// 只保留按键与修饰键用于查找；字符的大小写已体现 Shift，因此忽略 Shift
+fn normalize(key: &KeyEvent) -> KeyEvent {
+    let mut modifiers = key.modifiers & (KeyModifiers::CONTROL | KeyModifiers::ALT);
+    if !matches!(key.code, KeyCode::Char(_)) {
+        modifiers |= key.modifiers & KeyModifiers::SHIFT;
+    }
+    KeyEvent::new(key.code, modifiers)
+}
+
+/// 按键在帮助栏中的显示名称
+fn key_label(key: &KeyEvent) -> String {
+    let name = match NAMED_KEYS.iter().find(|(_, code, _)| *code == key.code) {
+        Some((_, _, label)) => label.to_string(),
+        None => match key.code {
+            KeyCode::Char(ch) => ch.to_string(),
+            other => format!("{other:?}"),
+        },
+    };
+    if key.modifiers.contains(KeyModifiers::CONTROL) {
+        format!("Ctrl+{name}")
+    } else {
+        name
+    }
+}
@@ -1,41 +1,486 @@
 //! 应用主入口与事件循环
+//!
+//! 除交互界面外，扫描与分析功能都可以作为库使用，不涉及终端：
+//! - [`App::scan`] / [`App::new`] 扫描文件树（[`App::scan_in_background`] 在后台扫描），[`App::from_node`] 包装已有的树，结果在 [`App::node`] 中
+//! - [`model`]：[`Node`] 及扫描选项，[`Node::to_json`] / [`Node::export_ncdu`] 导出
+//! - [`report`]：重复文件、扩展名统计等报告
+//! - [`vfs`]：扫描使用的文件系统访问接口，[`MemoryFs`](vfs::MemoryFs) 提供内存中的虚拟树
+//! - [`cache`]：保存与加载扫描结果
+//! - [`error`]：扫描失败时返回的 [`FswhyError`]
+//! - [`format`](mod@format)：大小与时间的格式化
+//! - [`App::write_tree`]：按 [`ViewOptions`] 把可见列表写入任意 [`Write`](std::io::Write)
+//! - [`key_action`]：把按键映射为 [`Action`]，不读取终端
+//!
+//! 只有 [`App::run`]（占用终端）与 [`App::print`]（写入标准输出）依赖终端或标准输出。
 
-use crate::model::Node;
+use crate::config::Preferences;
+use crate::format::SizeFormat;
+use crate::model::{Node, ProgressFn, ScanError, ScanOptions, ScanProgress, ScanStats, SortOrder};
 use crate::theme::load_theme_from_env_or_default;
-use crate::ui_state::UiState;
+use crate::ui_state::{DEFAULT_LARGE_FILE_THRESHOLD, StatusMessage, UiState};
+use std::io::IsTerminal;
 use std::path::PathBuf;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, mpsc};
+use std::thread;
+use std::time::{Duration, Instant};
 
+pub mod cache;
+pub mod config;
+pub mod error;
+mod export;
+pub mod format;
+mod ignore;
+mod keys;
 pub mod model;
+pub mod report;
 mod theme;
 mod ui;
 mod ui_state;
+pub mod vfs;
+
+pub use error::FswhyError;
+pub use keys::KeyMap;
+pub use ui::key_action;
+pub use ui_state::{Action, DisplayColumn, InputMode};
+
+/// 界面初始设置
+#[derive(Clone, Debug)]
+pub struct ViewOptions {
+    /// 首次渲染前预先展开的目录深度，0 表示只展开根节点
+    pub expand_depth: usize,
+    /// 是否使用颜色；即使开启，标准输出不是终端或设置了 `NO_COLOR` 时也会禁用
+    pub color: bool,
+    /// 不小于此大小（字节）的文件以醒目颜色显示
+    pub large_file_threshold: u64,
+    /// 大小的显示方式（单位的进制与小数位数）
+    pub size_format: SizeFormat,
+    /// 只显示不小于此大小（字节）的条目，0 表示不限；不修改文件树
+    pub min_size: u64,
+    /// 是否显示隐藏条目（名称以 `.` 开头）；隐藏时大小仍计入父目录，可在界面中切换
+    pub show_hidden: bool,
+    /// 是否只显示目录（文件大小仍计入目录），可在界面中切换
+    pub dirs_only: bool,
+    /// 初始排序方式
+    pub sort: SortOrder,
+    /// 排序时目录是否优先于文件
+    pub group_dirs_first: bool,
+    /// 初始的行尾显示信息
+    pub column: DisplayColumn,
+}
+
+impl Default for ViewOptions {
+    fn default() -> Self {
+        Self {
+            expand_depth: 0,
+            color: true,
+            large_file_threshold: DEFAULT_LARGE_FILE_THRESHOLD,
+            size_format: SizeFormat::default(),
+            min_size: 0,
+            show_hidden: true,
+            dirs_only: false,
+            sort: SortOrder::SizeDesc,
+            group_dirs_first: true,
+            column: DisplayColumn::Size,
+        }
+    }
+}
+
+/// 标准输出是终端且未设置 `NO_COLOR` 时才输出颜色
+fn color_supported() -> bool {
+    let no_color = std::env::var_os("NO_COLOR").is_some_and(|v| !v.is_empty());
+    !no_color && std::io::stdout().is_terminal()
+}
+
+/// 将界面中可修改的偏好写回偏好文件，文件中的其他设置保持不变
+fn save_preferences(path: &std::path::Path, state: &UiState) -> anyhow::Result<()> {
+    let preferences = Preferences {
+        sort: state.sort_mode,
+        column: state.column,
+        show_hidden: state.show_hidden,
+        ..Preferences::load(path)?
+    };
+    preferences.save(path)
+}
+
+/// 扫描结果：文件树、被跳过的条目、扫描统计与是否被中断
+type ScanOutcome = (Node, Vec<ScanError>, ScanStats, bool);
+
+/// 检查待扫描的路径：至少一个，且都是目录
+fn check_scan_paths(paths: &[PathBuf]) -> Result<(), FswhyError> {
+    if paths.is_empty() {
+        return Err(FswhyError::NoPath);
+    }
+    for path in paths {
+        let meta = std::fs::metadata(path).map_err(|e| FswhyError::from_io(path, e))?;
+        if !meta.is_dir() {
+            return Err(FswhyError::NotADirectory(path.clone()));
+        }
+    }
+    Ok(())
+}
+
+/// 依次扫描各路径；多个路径时以 [`Node::multi_root`] 合成的根目录作为各文件树的父节点
+///
+/// 返回文件树、被跳过的条目、扫描统计与是否被中断。扫描被取消时不再扫描剩余的路径。
+fn scan_paths(
+    paths: &[PathBuf],
+    options: &ScanOptions,
+    progress: &mut ProgressFn<'_>,
+) -> Result<ScanOutcome, FswhyError> {
+    let mut roots = Vec::with_capacity(paths.len());
+    let mut skipped = Vec::new();
+    let mut stats = ScanStats::default();
+    let mut interrupted = false;
+    for path in paths {
+        let report = Node::scan_with_progress(path.clone(), options.clone(), progress)?;
+        roots.push(report.root);
+        skipped.extend(report.errors);
+        stats.merge(report.stats);
+        if report.interrupted {
+            interrupted = true;
+            break;
+        }
+    }
+    let node = if roots.len() == 1 {
+        roots.remove(0)
+    } else {
+        Node::multi_root(roots)
+    };
+    Ok((node, skipped, stats, interrupted))
+}
+
+/// 后台扫描线程发给界面的消息
+enum ScanUpdate {
+    /// 已扫描的项数
+    Progress(usize),
+    /// 根路径的一个直接子条目扫描完成
+    Subtree(Node),
+    /// 扫描结束，内容同 [`scan_paths`] 的返回值
+    Finished(Result<ScanOutcome, FswhyError>),
+}
+
+/// 进行中的后台扫描
+struct BackgroundScan {
+    updates: mpsc::Receiver<ScanUpdate>,
+    /// 退出界面时置位以停止扫描
+    cancel: Arc<AtomicBool>,
+}
+
+/// 后台扫描期间界面的刷新间隔
+const SCAN_REFRESH: Duration = Duration::from_millis(100);
+
+/// 被跳过条目的摘要，见 [`App::skipped_summary`]
+fn summarize_skipped(skipped: &[ScanError]) -> Option<String> {
+    if skipped.is_empty() {
+        return None;
+    }
+    let mut counts: Vec<(std::io::ErrorKind, usize)> = Vec::new();
+    for error in skipped {
+        match counts.iter_mut().find(|(kind, _)| *kind == error.kind) {
+            Some((_, count)) => *count += 1,
+            None => counts.push((error.kind, 1)),
+        }
+    }
+    counts.sort_by_key(|&(_, count)| std::cmp::Reverse(count));
+
+    let count_of = |is_dir| skipped.iter().filter(|e| e.is_dir == is_dir).count();
+    let parts: Vec<String> = [
+        (count_of(Some(true)), "directory", "directories"),
+        (count_of(Some(false)), "file", "files"),
+        (count_of(None), "other entry", "other entries"),
+    ]
+    .into_iter()
+    .filter(|&(count, _, _)| count > 0)
+    .map(|(count, one, many)| format!("{count} {}", if count == 1 { one } else { many }))
+    .collect();
+    let what = match parts.as_slice() {
+        [.., last] if parts.len() > 1 => {
+            format!("{} and {last}", parts[..parts.len() - 1].join(", "))
+        }
+        _ => parts.concat(),
+    };
+    let reasons = match counts.as_slice() {
+        [(kind, _)] => kind.to_string(),
+        _ => counts
+            .iter()
+            .map(|(kind, count)| format!("{count} {kind}"))
+            .collect::<Vec<_>>()
+            .join(", "),
+    };
+    Some(format!(
+        "Totals exclude {what} that could not be read ({reasons})"
+    ))
+}
 
 /// 应用容器，持有文件树根节点
 pub struct App {
     pub node: Node,
+    pub view: ViewOptions,
+    /// 扫描时被跳过的条目
+    pub skipped: Vec<ScanError>,
+    /// 扫描统计，从缓存加载时为 `None`
+    pub stats: Option<ScanStats>,
+    /// 扫描是否被中断，此时文件树不完整
+    pub interrupted: bool,
+    /// 界面偏好文件；设置后在界面中修改排序、显示列或隐藏条目的显示时写回该文件
+    pub preferences_path: Option<PathBuf>,
+    /// 监视模式的重新扫描间隔；设置后 [`App::run`] 按此间隔重新扫描并高亮大小变化的条目，
+    /// 只对由 [`App::new`] 扫描得到的应用有效
+    pub watch: Option<Duration>,
+    /// 普通模式下的按键绑定
+    pub keys: KeyMap,
+    /// 扫描的路径与选项，供监视模式重新扫描；从已有文件树创建时为 `None`
+    source: Option<(Vec<PathBuf>, ScanOptions)>,
+    /// 进行中的后台扫描，见 [`App::scan_in_background`]
+    scan: Option<BackgroundScan>,
 }
 
 impl App {
-    /// 扫描指定路径并初始化应用
-    pub fn new(path: PathBuf) -> anyhow::Result<Self> {
-        let root = Node::scan(path)?;
-        Ok(Self { node: root })
+    /// 按 `options` 扫描指定路径并初始化应用，扫描进度通过 `progress` 报告
+    ///
+    /// 指定多个路径时依次扫描，并以 [`Node::multi_root`] 合成的根目录作为各文件树的父节点；
+    /// 扫描被取消时不再扫描剩余的路径。
+    ///
+    /// # 错误
+    /// 没有指定路径时返回 [`FswhyError::NoPath`]；任一路径不是目录（如普通文件）时返回
+    /// [`FswhyError::NotADirectory`]，而不是显示只有一个节点的树；无法访问时的错误同 [`Node::scan`]。
+    pub fn new(
+        paths: Vec<PathBuf>,
+        options: ScanOptions,
+        progress: &mut ProgressFn<'_>,
+    ) -> Result<Self, FswhyError> {
+        check_scan_paths(&paths)?;
+        let (node, skipped, stats, interrupted) = scan_paths(&paths, &options, progress)?;
+        Ok(Self {
+            skipped,
+            stats: Some(stats),
+            interrupted,
+            source: Some((paths, options)),
+            ..Self::from_node(node)
+        })
+    }
+
+    /// 在后台线程中按 `options` 扫描指定路径并立即返回，扫描完成前即可在 [`App::run`] 中浏览部分结果
+    ///
+    /// 文件树起初只有根目录，根路径的直接子条目扫描完成后逐个加入，扫描结束后替换为完整的文件树；
+    /// 扫描期间界面显示扫描指示，不能删除或重新扫描。扫描结束前 [`App::stats`] 为 `None`，
+    /// 退出界面时扫描通过 `options.cancel`（未设置时自动创建）取消。
+    ///
+    /// # 错误
+    /// 同 [`App::new`]。
+    pub fn scan_in_background(
+        paths: Vec<PathBuf>,
+        options: ScanOptions,
+    ) -> Result<Self, FswhyError> {
+        check_scan_paths(&paths)?;
+        let cancel = options.cancel.clone().unwrap_or_default();
+        let options = ScanOptions {
+            cancel: Some(Arc::clone(&cancel)),
+            ..options
+        };
+
+        let (sender, updates) = mpsc::channel();
+        let (scan_paths_, scan_options) = (paths.clone(), options.clone());
+        thread::spawn(move || {
+            // 依次扫描多个路径时累计已扫描的项数
+            let mut finished_items = 0;
+            let mut progress = |event: ScanProgress<'_>| {
+                let update = match event {
+                    ScanProgress::Scanned { items, .. } => {
+                        ScanUpdate::Progress(finished_items + items)
+                    }
+                    ScanProgress::SubtreeFinished { node } => ScanUpdate::Subtree(node.clone()),
+                    ScanProgress::Finished { items } => {
+                        finished_items += items;
+                        return;
+                    }
+                    _ => return,
+                };
+                // 界面已退出时没有接收者，忽略发送失败
+                let _ = sender.send(update);
+            };
+            let result = scan_paths(&scan_paths_, &scan_options, &mut progress);
+            let _ = sender.send(ScanUpdate::Finished(result));
+        });
+
+        let mut roots: Vec<Node> = paths.iter().cloned().map(Node::empty_dir).collect();
+        let node = if roots.len() == 1 {
+            roots.remove(0)
+        } else {
+            Node::multi_root(roots)
+        };
+        Ok(Self {
+            source: Some((paths, options)),
+            scan: Some(BackgroundScan { updates, cancel }),
+            ..Self::from_node(node)
+        })
+    }
+
+    /// 以默认扫描选项扫描单个路径，不报告进度
+    ///
+    /// # 错误
+    /// 同 [`App::new`]。
+    pub fn scan(path: impl Into<PathBuf>) -> Result<Self, FswhyError> {
+        Self::new(vec![path.into()], ScanOptions::default(), &mut |_| {})
+    }
+
+    /// 使用已有的文件树（如从缓存加载）初始化应用
+    pub fn from_node(node: Node) -> Self {
+        Self {
+            node,
+            view: ViewOptions::default(),
+            skipped: Vec::new(),
+            stats: None,
+            interrupted: false,
+            preferences_path: None,
+            watch: None,
+            keys: KeyMap::default(),
+            source: None,
+            scan: None,
+        }
+    }
+
+    /// 被跳过条目的摘要，如 `Totals exclude 3 directories and 2 files that could not be read (permission denied)`；
+    /// 没有跳过时为 `None`。包含这些条目的目录大小只是下限，见 [`Node::size_is_partial`]
+    pub fn skipped_summary(&self) -> Option<String> {
+        summarize_skipped(&self.skipped)
     }
 
     /// 创建UI状态
-    fn create_ui_state(&self) -> UiState<'_> {
+    fn create_ui_state(&mut self) -> UiState<'_> {
         let theme = load_theme_from_env_or_default();
-        UiState::new(&self.node, theme)
+        let skipped = self.skipped_summary();
+        let mut state = UiState::new(&mut self.node, theme);
+        state.expand_to_depth(self.view.expand_depth);
+        state.color = self.view.color && color_supported();
+        state.large_file_threshold = self.view.large_file_threshold;
+        state.size_format = self.view.size_format;
+        state.min_size = self.view.min_size;
+        state.show_hidden = self.view.show_hidden;
+        state.dirs_only = self.view.dirs_only;
+        state.sort_mode = self.view.sort;
+        state.group_dirs_first = self.view.group_dirs_first;
+        state.column = self.view.column;
+        state.interrupted = self.interrupted;
+        state.keys = self.keys.clone();
+        if let Some((_, options)) = &self.source {
+            state.scan_options = options.clone();
+        }
+        state.status = skipped.map(|text| StatusMessage {
+            text,
+            is_error: true,
+        });
+        state
+    }
+
+    /// 非交互模式：按 `expand_depth` 展开后输出一次可见列表到标准输出
+    pub fn print(&mut self) -> std::io::Result<()> {
+        let state = self.create_ui_state();
+        ui::write_tree(&state, &mut std::io::stdout().lock())
+    }
+
+    /// 按 `expand_depth` 展开后将可见列表写入 `out`，不检查也不占用终端
+    ///
+    /// 颜色只由 [`ViewOptions::color`] 决定，写入文件时通常应关闭。
+    pub fn write_tree(&mut self, out: &mut impl std::io::Write) -> std::io::Result<()> {
+        let color = self.view.color;
+        let mut state = self.create_ui_state();
+        state.color = color;
+        ui::write_tree(&state, out)
     }
 
     /// 主循环：渲染 → 输入 → 更新
-    pub fn run(&self) -> anyhow::Result<()> {
+    pub fn run(&mut self) -> anyhow::Result<()> {
+        let preferences_path = self.preferences_path.clone();
+        let watch = self.watch.zip(self.source.clone());
+        let mut background = self.scan.take();
+        let mut finished = None;
         let mut state = self.create_ui_state();
-        loop {
+        state.scanning = background.as_ref().map(|_| 0);
+        // 监视模式在后台扫描结束后才开始计时
+        let mut next_scan = watch
+            .as_ref()
+            .filter(|_| background.is_none())
+            .map(|(interval, _)| Instant::now() + *interval);
+        let mut saved = (state.sort_mode, state.column, state.show_hidden);
+        let _guard = ui::RawModeGuard::enter()?;
+        let result = loop {
+            if let Some(result) = background
+                .as_ref()
+                .and_then(|scan| receive_updates(scan, &mut state))
+            {
+                background = None;
+                state.scanning = None;
+                match result {
+                    Ok((node, skipped, stats, interrupted)) => {
+                        state.replace_tree(node);
+                        // 与部分结果相比的大小变化没有意义
+                        state.size_changes.clear();
+                        state.interrupted = interrupted;
+                        if let Some(text) = summarize_skipped(&skipped) {
+                            state.status = Some(StatusMessage {
+                                text,
+                                is_error: true,
+                            });
+                        }
+                        finished = Some((skipped, stats, interrupted));
+                    }
+                    Err(e) => {
+                        state.status = Some(StatusMessage {
+                            text: format!("Scan failed: {e:#}"),
+                            is_error: true,
+                        })
+                    }
+                }
+                next_scan = watch
+                    .as_ref()
+                    .map(|(interval, _)| Instant::now() + *interval);
+            }
+            if let Some(height) = ui::viewport_height(&state) {
+                state.viewport_height = height;
+            }
+            if let Some(width) = ui::terminal_width() {
+                state.viewport_width = width;
+            }
+            state.scroll_to_cursor();
             ui::render(&state);
 
-            let action = match ui::get_input() {
-                Ok(action) => action,
+            // 后台扫描期间定时刷新；等待删除确认时不重新扫描
+            let timeout = if background.is_some() {
+                Some(SCAN_REFRESH)
+            } else {
+                next_scan
+                    .filter(|_| state.input_mode != InputMode::Confirm)
+                    .map(|at| at.saturating_duration_since(Instant::now()))
+            };
+            let action = match ui::get_input(&state, timeout) {
+                Ok(Some(action)) => action,
+                Ok(None) => {
+                    if background.is_none()
+                        && let Some((interval, (paths, options))) = &watch
+                    {
+                        // 终端处于原始模式，Ctrl-C 不产生信号，重新扫描不使用取消标志
+                        let options = ScanOptions {
+                            cancel: None,
+                            ..options.clone()
+                        };
+                        match scan_paths(paths, &options, &mut |_| {}) {
+                            Ok((node, _, _, interrupted)) => {
+                                state.replace_tree(node);
+                                state.interrupted = interrupted;
+                            }
+                            Err(e) => {
+                                state.status = Some(StatusMessage {
+                                    text: format!("Rescan failed: {e:#}"),
+                                    is_error: true,
+                                })
+                            }
+                        }
+                        next_scan = Some(Instant::now() + *interval);
+                    }
+                    continue;
+                }
                 Err(e) => {
                     eprintln!("⚠️ Input error: {e}");
                     continue;
@@ -44,9 +489,72 @@ impl App {
 
             match state.update(action) {
                 Ok(false) => break Ok(()),
-                Ok(true) => continue,
+                Ok(true) => {}
                 Err(e) => eprintln!("⚠️{e}"),
             }
+
+            // 界面偏好变化时写回偏好文件
+            let current = (state.sort_mode, state.column, state.show_hidden);
+            if current != saved {
+                saved = current;
+                if let Some(path) = &preferences_path
+                    && let Err(e) = save_preferences(path, &state)
+                {
+                    state.status = Some(StatusMessage {
+                        text: format!("Cannot save preferences: {e:#}"),
+                        is_error: true,
+                    });
+                }
+            }
+        };
+
+        // 退出时扫描仍在进行则取消；已结束的扫描结果保留在应用中
+        if let Some(scan) = background {
+            scan.cancel.store(true, Ordering::Relaxed);
+        }
+        if let Some((skipped, stats, interrupted)) = finished {
+            self.skipped = skipped;
+            self.stats = Some(stats);
+            self.interrupted = interrupted;
+        }
+        result
+    }
+}
+
+/// 把后台扫描已完成的子树与进度交给界面，扫描结束时返回其结果
+fn receive_updates(
+    scan: &BackgroundScan,
+    state: &mut UiState,
+) -> Option<Result<ScanOutcome, FswhyError>> {
+    let mut subtrees = Vec::new();
+    let mut result = None;
+    for update in scan.updates.try_iter() {
+        match update {
+            ScanUpdate::Progress(items) => state.scanning = Some(items),
+            ScanUpdate::Subtree(node) => subtrees.push(node),
+            ScanUpdate::Finished(scan_result) => result = Some(scan_result),
+        }
+    }
+    if !subtrees.is_empty() {
+        state.insert_subtrees(subtrees);
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_rejects_file_root() {
+        // 包的清单文件一定是普通文件
+        let path = PathBuf::from(env!("CARGO_MANIFEST_DIR")).join("Cargo.toml");
+        match App::scan(path.clone()) {
+            Err(FswhyError::NotADirectory(reported)) => assert_eq!(reported, path),
+            Err(e) => panic!("unexpected error: {e}"),
+            Ok(_) => panic!("scanning a file should fail"),
         }
+        let result = App::scan_in_background(vec![path], ScanOptions::default());
+        assert!(matches!(result, Err(FswhyError::NotADirectory(_))));
     }
 }
@@ -1,14 +1,807 @@
-use anyhow::Result;
-use fswhy::App;
+use anyhow::{Context, Result, bail};
+use fswhy::cache::CacheHeader;
+use fswhy::config::Preferences;
+use fswhy::format::{
+    MAX_SIZE_PRECISION, SizeBase, SizeFormat, format_delta, format_size, parse_size,
+};
+use fswhy::model::{Node, ScanOptions, ScanProgress, ScanStats};
+use fswhy::report::{NO_EXTENSION, reclaimable, user_names};
+use fswhy::{App, KeyMap};
+use signal_hook::consts::SIGINT;
 use std::env;
-use std::path::PathBuf;
-fn main() -> Result<()> {
-    let root_path: PathBuf = env::args()
-        .nth(1)
-        .map(PathBuf::from)
-        .unwrap_or(env::current_dir()?);
+use std::fs::File;
+use std::io::{self, BufReader, BufWriter, Write};
+use std::path::{Path, PathBuf};
+use std::process;
+use std::str::FromStr;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::Duration;
+
+/// 命令行参数
+#[derive(Default)]
+struct Args {
+    paths: Vec<PathBuf>,
+    paths_from: Option<PathBuf>,
+    json: bool,
+    export_ncdu: Option<PathBuf>,
+    csv: Option<PathBuf>,
+    expand_depth: Option<usize>,
+    print: bool,
+    save: Option<PathBuf>,
+    load: Option<PathBuf>,
+    filter: Option<String>,
+    keep_dir_sizes: bool,
+    min_size: Option<u64>,
+    no_color: bool,
+    large_file_threshold: Option<u64>,
+    si: bool,
+    precision: Option<usize>,
+    size_only: bool,
+    check: Option<u64>,
+    find_empty: bool,
+    zero_byte: bool,
+    duplicates: bool,
+    by_ext: bool,
+    by_owner: bool,
+    by_dir: Option<usize>,
+    deepest: Option<usize>,
+    summary: bool,
+    anomalies: bool,
+    no_hidden: bool,
+    no_dirs_first: bool,
+    quiet: bool,
+    skip_hidden: bool,
+    dirs_only: bool,
+    stats_depth: Option<usize>,
+    one_file_system: bool,
+    excludes: Vec<String>,
+    watch: Option<u64>,
+    diff: Option<(PathBuf, PathBuf)>,
+    max_children: Option<usize>,
+    include_dir_size: bool,
+    time_budget: Option<u64>,
+    xattrs: bool,
+}
+
+impl Args {
+    /// 解析命令行参数
+    fn parse() -> Result<Self> {
+        let mut args = Args::default();
+        let mut iter = env::args().skip(1);
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--json" => args.json = true,
+                "--no-color" => args.no_color = true,
+                "--si" => args.si = true,
+                "--size-only" => args.size_only = true,
+                "--find-empty" => args.find_empty = true,
+                "--zero-byte" => args.zero_byte = true,
+                "--duplicates" => args.duplicates = true,
+                "--by-ext" => args.by_ext = true,
+                "--by-owner" => args.by_owner = true,
+                "--summary" => args.summary = true,
+                "--by-dir" => args.by_dir = Some(parse_value(&mut iter, &arg)?),
+                "--deepest" => args.deepest = Some(parse_value(&mut iter, &arg)?),
+                "--anomalies" => args.anomalies = true,
+                "--no-hidden" => args.no_hidden = true,
+                "--no-dirs-first" => args.no_dirs_first = true,
+                "--quiet" => args.quiet = true,
+                "--skip-hidden" => args.skip_hidden = true,
+                "--dirs-only" => args.dirs_only = true,
+                "--one-file-system" => args.one_file_system = true,
+                "--include-dir-size" => args.include_dir_size = true,
+                "--xattrs" => args.xattrs = true,
+                "--stats-depth" => args.stats_depth = Some(parse_value(&mut iter, &arg)?),
+                // `--large-file-threshold` 为旧名称
+                "--highlight-above" | "--large-file-threshold" => {
+                    args.large_file_threshold = Some(parse_size(&value_of(&mut iter, &arg)?)?)
+                }
+                "--export-ncdu" => args.export_ncdu = Some(value_of(&mut iter, &arg)?.into()),
+                "--exclude" => args.excludes.push(value_of(&mut iter, &arg)?),
+                "--watch" => args.watch = Some(parse_value(&mut iter, &arg)?),
+                "--max-children" => args.max_children = Some(parse_value(&mut iter, &arg)?),
+                "--precision" => args.precision = Some(parse_value(&mut iter, &arg)?),
+                "--time-budget" => args.time_budget = Some(parse_value(&mut iter, &arg)?),
+                "--csv" => args.csv = Some(value_of(&mut iter, &arg)?.into()),
+                "--expand-depth" => args.expand_depth = Some(parse_value(&mut iter, &arg)?),
+                "--print" => args.print = true,
+                "--save" => args.save = Some(value_of(&mut iter, &arg)?.into()),
+                "--load" => args.load = Some(value_of(&mut iter, &arg)?.into()),
+                "--paths-from" => args.paths_from = Some(value_of(&mut iter, &arg)?.into()),
+                "--diff" => {
+                    let old = value_of(&mut iter, &arg)?.into();
+                    args.diff = Some((old, value_of(&mut iter, &arg)?.into()));
+                }
+                "--filter" => args.filter = Some(value_of(&mut iter, &arg)?),
+                "--keep-dir-sizes" => args.keep_dir_sizes = true,
+                "--min-size" => args.min_size = Some(parse_size(&value_of(&mut iter, &arg)?)?),
+                "--check" => args.check = Some(parse_size(&value_of(&mut iter, &arg)?)?),
+                flag if flag.starts_with("--") => bail!("Unknown option: {flag}"),
+                _ => args.paths.push(PathBuf::from(arg)),
+            }
+        }
+        Ok(args)
+    }
+}
+
+impl Args {
+    /// 由命令行选项得到的扫描选项
+    fn scan_options(&self) -> ScanOptions {
+        let defaults = ScanOptions::default();
+        ScanOptions {
+            skip_hidden: self.skip_hidden,
+            stats_depth: self.stats_depth.unwrap_or(defaults.stats_depth),
+            same_filesystem: self.one_file_system,
+            extra_ignores: self.excludes.clone(),
+            max_children_per_dir: self.max_children,
+            include_dir_size: self.include_dir_size,
+            time_budget: self.time_budget.map(Duration::from_secs),
+            count_xattrs: self.xattrs,
+            // 摘要只需要根目录的直接子节点，更深的目录只统计大小
+            max_depth: if self.summary {
+                Some(1)
+            } else {
+                defaults.max_depth
+            },
+            ..defaults
+        }
+    }
+}
+
+/// 读取选项的参数值
+fn value_of(iter: &mut impl Iterator<Item = String>, flag: &str) -> Result<String> {
+    iter.next()
+        .with_context(|| format!("Missing value for {flag}"))
+}
+
+/// 读取并解析选项的参数值
+fn parse_value<T>(iter: &mut impl Iterator<Item = String>, flag: &str) -> Result<T>
+where
+    T: FromStr,
+    T::Err: std::error::Error + Send + Sync + 'static,
+{
+    let value = value_of(iter, flag)?;
+    value
+        .parse()
+        .with_context(|| format!("Invalid value for {flag}: {value}"))
+}
+
+/// 进度行中旋转指示器的各帧
+const SPINNER: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+
+/// 进度条的宽度（字符数）
+const PROGRESS_BAR_WIDTH: usize = 20;
+
+/// 默认进度显示：输出到标准错误，大小按 `size_format` 格式化
+///
+/// 总项数未知时显示旋转指示器与吞吐量；有估计值（如上次保存的缓存中的项数）时显示百分比进度条，
+/// 实际项数超出估计时停在 99%。
+struct ProgressDisplay {
+    size_format: SizeFormat,
+    /// 预计的总项数
+    estimate: Option<usize>,
+    /// 已显示的进度次数，用于选择旋转指示器的帧
+    ticks: usize,
+}
+
+impl ProgressDisplay {
+    fn new(size_format: SizeFormat, estimate: Option<usize>) -> Self {
+        Self {
+            size_format,
+            estimate: estimate.filter(|&n| n > 0),
+            ticks: 0,
+        }
+    }
+
+    /// 处理一个进度事件
+    fn update(&mut self, event: ScanProgress) {
+        let size_format = self.size_format;
+        match event {
+            ScanProgress::Started { path } => eprintln!("Scanning {}...", path.display()),
+            ScanProgress::Scanned {
+                items,
+                bytes,
+                elapsed,
+                ..
+            } => {
+                let secs = elapsed.as_secs_f64().max(f64::EPSILON);
+                let throughput = format!(
+                    "{} ({:.0} items/s, {}/s)",
+                    format_size(bytes, size_format),
+                    items as f64 / secs,
+                    format_size((bytes as f64 / secs) as u64, size_format),
+                );
+                let indicator = match self.estimate {
+                    Some(total) => {
+                        let percent = (items * 100 / total).min(99);
+                        let filled = percent * PROGRESS_BAR_WIDTH / 100;
+                        format!(
+                            "[{}{}] {percent:>2}% {items}/~{total} items",
+                            "#".repeat(filled),
+                            "-".repeat(PROGRESS_BAR_WIDTH - filled),
+                        )
+                    }
+                    None => format!(
+                        "{} Scanned {items} items",
+                        SPINNER[self.ticks % SPINNER.len()]
+                    ),
+                };
+                self.ticks += 1;
+                // 行尾留空格覆盖上一次更长的输出
+                eprint!("\r{indicator}, {throughput}   ");
+                io::stderr().flush().ok();
+            }
+            ScanProgress::DirFinished {
+                path,
+                dirs,
+                files,
+                size,
+                elapsed,
+            } => eprintln!(
+                "\n✓ {} ({} dirs, {} files, {}) in {:.2}s",
+                path.display(),
+                dirs,
+                files,
+                format_size(size, size_format),
+                elapsed.as_secs_f64(),
+            ),
+            ScanProgress::SubtreeFinished { .. } => {}
+            ScanProgress::Finished { .. } => eprintln!(),
+        }
+    }
+}
+
+/// 缓存文件的根路径为 `root` 时，其中记录的文件与目录数（不含根目录），用作进度估计
+///
+/// 使用目录统计而不是遍历节点，超出最大深度的子树也计算在内。
+fn cached_item_count(cache: &Path, root: &Path) -> Option<usize> {
+    let (header, node) = read_cache(cache).ok()?;
+    (header.root == root)
+        .then(|| (node.descendant_file_count() + node.descendant_dir_count()) as usize)
+}
+
+/// 输出扫描总结
+fn print_stats(stats: &ScanStats, size_format: SizeFormat) {
+    eprintln!(
+        "Total: {} files, {} dirs (max depth {}), {} in {:.2}s",
+        stats.files,
+        stats.dirs,
+        stats.max_depth,
+        format_size(stats.bytes, size_format),
+        stats.elapsed.as_secs_f64(),
+    );
+    if stats.xattr_bytes > 0 {
+        eprintln!(
+            "Extended attributes: {} (included in the total)",
+            format_size(stats.xattr_bytes, size_format)
+        );
+    }
+    if let Some((path, size)) = &stats.largest_file {
+        eprintln!(
+            "Largest file: {} ({})",
+            path.display(),
+            format_size(*size, size_format)
+        );
+    }
+}
+
+/// 只输出各路径的总大小，不构建文件树
+fn print_sizes(paths: &[PathBuf], options: &ScanOptions, size_format: SizeFormat) -> Result<()> {
+    for path in paths {
+        let size = Node::quick_size_with_options(path, options)
+            .with_context(|| format!("Cannot access {}", path.display()))?;
+        println!("{}\t{}", format_size(size, size_format), path.display());
+    }
+    Ok(())
+}
+
+/// 输出各路径的总大小，返回是否都不超过 `limit`；超出的路径在标准错误中说明
+fn check_sizes(
+    paths: &[PathBuf],
+    limit: u64,
+    options: &ScanOptions,
+    size_format: SizeFormat,
+) -> Result<bool> {
+    let mut within = true;
+    for path in paths {
+        let size = Node::quick_size_with_options(path, options)
+            .with_context(|| format!("Cannot access {}", path.display()))?;
+        println!("{}\t{}", format_size(size, size_format), path.display());
+        if size > limit {
+            eprintln!(
+                "❌ {} exceeds {} ({})",
+                path.display(),
+                format_size(limit, size_format),
+                format_size(size, size_format)
+            );
+            within = false;
+        }
+    }
+    Ok(within)
+}
 
-    let app = App::new(root_path)?;
+/// 输出重复文件组及可回收的空间
+fn print_duplicates(node: &Node, size_format: SizeFormat) {
+    let groups = node.find_duplicates();
+    for group in &groups {
+        println!(
+            "{} copies of {} ({} reclaimable)",
+            group.len(),
+            format_size(group[0].size(), size_format),
+            format_size(reclaimable(group), size_format)
+        );
+        for node in group {
+            println!("  {}", node.path().display());
+        }
+    }
+    let total: u64 = groups.iter().map(|group| reclaimable(group)).sum();
+    println!(
+        "{} duplicate groups, {} reclaimable",
+        groups.len(),
+        format_size(total, size_format)
+    );
+}
+
+/// 按总大小降序输出各扩展名的用量
+fn print_extensions(node: &Node, size_format: SizeFormat) {
+    let mut entries: Vec<_> = node.size_by_extension().into_iter().collect();
+    entries.sort_by(|(a_ext, (a_size, _)), (b_ext, (b_size, _))| {
+        b_size.cmp(a_size).then_with(|| a_ext.cmp(b_ext))
+    });
+    for (ext, (size, count)) in entries {
+        let label = if ext == NO_EXTENSION {
+            ext
+        } else {
+            format!(".{ext}")
+        };
+        let noun = if count == 1 { "file" } else { "files" };
+        println!(
+            "{:>10}  {label} ({count} {noun})",
+            format_size(size, size_format)
+        );
+    }
+}
+
+/// 按总大小降序输出各所有者的用量，能解析用户名时一并显示
+fn print_owners(node: &Node, size_format: SizeFormat) {
+    let names = user_names();
+    let mut entries: Vec<_> = node.size_by_owner().into_iter().collect();
+    entries.sort_by(|(a_uid, (a_size, _)), (b_uid, (b_size, _))| {
+        b_size.cmp(a_size).then_with(|| a_uid.cmp(b_uid))
+    });
+    for (uid, (size, count)) in entries {
+        let label = match names.get(&uid) {
+            Some(name) => format!("{name} (uid {uid})"),
+            None => format!("uid {uid}"),
+        };
+        let noun = if count == 1 { "file" } else { "files" };
+        println!(
+            "{:>10}  {label}, {count} {noun}",
+            format_size(size, size_format)
+        );
+    }
+}
+
+/// 按大小降序输出根目录的各直接子节点及其占比
+fn print_summary(node: &Node, size_format: SizeFormat) {
+    for (path, size, percent) in node.immediate_children_summary() {
+        println!(
+            "{:>10}  {percent:5.1}%  {}",
+            format_size(size, size_format),
+            path.display()
+        );
+    }
+}
+
+/// 输出最深的至多 `n` 个条目及其深度
+fn print_deepest(node: &Node, n: usize, size_format: SizeFormat) {
+    for (depth, entry) in node.deepest_paths(n) {
+        println!(
+            "{depth:>5}  {:>10}  {}",
+            format_size(entry.size(), size_format),
+            entry.path().display()
+        );
+    }
+}
+
+/// 输出空间最集中、互不包含的至多 `n` 个目录
+fn print_directories(node: &Node, n: usize, size_format: SizeFormat) {
+    for dir in node.largest_non_overlapping(n) {
+        println!(
+            "{:>10}  {}",
+            format_size(dir.size(), size_format),
+            dir.path().display()
+        );
+    }
+}
+
+/// 比较两个缓存文件，按变化量输出新增、移除与大小变化的条目，最后输出总的变化
+fn print_diff(old: &Path, new: &Path, size_format: SizeFormat) -> Result<()> {
+    let (_, old) = read_cache(old)?;
+    let (_, new) = read_cache(new)?;
+    for change in new.diff(&old) {
+        let status = match (change.old, change.new) {
+            (None, _) => "added",
+            (_, None) => "removed",
+            _ => "changed",
+        };
+        println!(
+            "{:>11}  {:<7}  {}",
+            format_delta(change.delta(), size_format),
+            status,
+            change.path.display()
+        );
+    }
+    println!(
+        "Net: {} ({} → {})",
+        format_delta(new.size() as i64 - old.size() as i64, size_format),
+        format_size(old.size(), size_format),
+        format_size(new.size(), size_format)
+    );
+    Ok(())
+}
+
+/// 输出表观大小与占用空间差异显著的文件
+fn print_anomalies(node: &Node, size_format: SizeFormat) {
+    for (path, apparent, allocated) in node.compression_anomalies() {
+        println!(
+            "{:>10} apparent  {:>10} on disk  {}",
+            format_size(apparent, size_format),
+            format_size(allocated, size_format),
+            path.display()
+        );
+    }
+}
+
+/// 按 `options` 扫描路径，扫描期间第一次 Ctrl-C 停止扫描并保留已扫描的部分，再次按下则直接退出
+///
+/// 超出时间预算而停止时同样保留已扫描的部分。`progress` 为 `None` 时不输出进度。
+fn scan(
+    paths: Vec<PathBuf>,
+    options: ScanOptions,
+    mut progress: Option<ProgressDisplay>,
+) -> Result<App> {
+    let cancel = Arc::new(AtomicBool::new(false));
+    // 标志已置位时恢复默认行为（终止进程），因此须先于设置标志的处理注册
+    signal_hook::flag::register_conditional_default(SIGINT, Arc::clone(&cancel))?;
+    signal_hook::flag::register(SIGINT, Arc::clone(&cancel))?;
+    let options = ScanOptions {
+        cancel: Some(Arc::clone(&cancel)),
+        ..options
+    };
+    let app = App::new(paths, options, &mut |event| {
+        if let Some(progress) = &mut progress {
+            progress.update(event);
+        }
+    })?;
+    // 扫描结束后 Ctrl-C 恢复为直接退出
+    let cancelled = cancel.swap(true, Ordering::Relaxed);
+    if app.interrupted && cancelled {
+        eprintln!("⚠️ Scan interrupted; showing partial results");
+    } else if app.interrupted {
+        eprintln!("⚠️ Scan time budget exceeded; showing partial results");
+    }
+    Ok(app)
+}
+
+/// 读取缓存文件
+fn read_cache(path: &Path) -> Result<(CacheHeader, Node)> {
+    let file = File::open(path).with_context(|| format!("Cannot open {}", path.display()))?;
+    Node::load_cache(BufReader::new(file))
+        .with_context(|| format!("Cannot load {}", path.display()))
+}
+
+/// 从文件（`-` 为标准输入）读取要扫描的路径，每行一个，忽略空行与 `#` 开头的注释
+///
+/// 无法访问或不是目录的路径只给出警告并跳过；没有可扫描的路径时返回错误。
+fn read_path_list(source: &Path) -> Result<Vec<PathBuf>> {
+    let stdin = source == Path::new("-");
+    let name = if stdin {
+        "standard input".into()
+    } else {
+        source.display().to_string()
+    };
+    let text = if stdin {
+        io::read_to_string(io::stdin())
+    } else {
+        std::fs::read_to_string(source)
+    }
+    .with_context(|| format!("Cannot read {name}"))?;
+    let mut paths = Vec::new();
+    for line in text.lines().map(str::trim) {
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let path = PathBuf::from(line);
+        match std::fs::metadata(&path) {
+            Ok(meta) if meta.is_dir() => paths.push(path),
+            Ok(_) => eprintln!("⚠️ Skipping {}: not a directory", path.display()),
+            Err(e) => eprintln!("⚠️ Skipping {}: {e}", path.display()),
+        }
+    }
+    if paths.is_empty() {
+        bail!("No directory to scan in {name}");
+    }
+    Ok(paths)
+}
+
+/// 从缓存文件加载文件树，根目录在缓存之后被修改过时给出提示
+fn load_cache(path: &Path) -> Result<App> {
+    let (header, node) = read_cache(path)?;
+    if header.is_stale() {
+        eprintln!(
+            "⚠️ {} may have changed since the cache was saved",
+            header.root.display()
+        );
+    }
+    Ok(App::from_node(node))
+}
+
+fn main() -> Result<()> {
+    let mut args = Args::parse()?;
+    if let Some(source) = &args.paths_from {
+        if args.load.is_some() || args.diff.is_some() {
+            bail!("--paths-from cannot be combined with --load or --diff");
+        }
+        let paths = read_path_list(source)?;
+        args.paths.extend(paths);
+    }
+    // 偏好文件提供默认值，命令行选项优先
+    let preferences_path = Preferences::default_path();
+    let (preferences, preferences_path) = match preferences_path.as_deref().map(Preferences::load) {
+        Some(Ok(preferences)) => (preferences, preferences_path),
+        Some(Err(e)) => {
+            // 不覆盖无法解析的文件
+            eprintln!("⚠️ {e:#}");
+            (Preferences::default(), None)
+        }
+        None => (Preferences::default(), None),
+    };
+    let precision = args.precision.unwrap_or(preferences.size_precision);
+    if precision > MAX_SIZE_PRECISION {
+        bail!("Size precision must be between 0 and {MAX_SIZE_PRECISION}, got {precision}");
+    }
+    let size_format = SizeFormat {
+        base: if args.si {
+            SizeBase::Decimal
+        } else {
+            SizeBase::Binary
+        },
+        precision,
+    };
+    if args.xattrs && !cfg!(feature = "xattr") {
+        bail!("--xattrs is not supported in this build (enable the `xattr` feature)");
+    }
+    // 只统计总大小的模式没有文件树，但遵循影响大小的扫描选项
+    if (args.size_only || args.check.is_some()) && args.time_budget.is_some() {
+        bail!("--time-budget cannot be combined with --size-only or --check");
+    }
+    if args.size_only {
+        if args.load.is_some() {
+            bail!("--size-only cannot be combined with --load");
+        }
+        let options = args.scan_options();
+        let paths = if args.paths.is_empty() {
+            vec![env::current_dir()?]
+        } else {
+            args.paths
+        };
+        return print_sizes(&paths, &options, size_format);
+    }
+    if let Some(limit) = args.check {
+        if args.load.is_some() {
+            bail!("--check cannot be combined with --load");
+        }
+        let options = args.scan_options();
+        let paths = if args.paths.is_empty() {
+            vec![env::current_dir()?]
+        } else {
+            args.paths
+        };
+        if !check_sizes(&paths, limit, &options, size_format)? {
+            process::exit(1);
+        }
+        return Ok(());
+    }
+    if let Some((old, new)) = &args.diff {
+        if !args.paths.is_empty() || args.load.is_some() {
+            bail!("--diff compares two cache files and cannot be combined with a path or --load");
+        }
+        return print_diff(old, new, size_format);
+    }
+    // 在扫描之前检查按键绑定，避免扫描完成后才报错
+    let keys = KeyMap::with_overrides(&preferences.keys).with_context(|| {
+        let path = preferences_path.as_deref().unwrap_or(Path::new(""));
+        format!("Invalid key bindings in {}", path.display())
+    })?;
+    let group_dirs_first = preferences.group_dirs_first && !args.no_dirs_first;
+    // 交互界面在后台扫描，扫描期间即可浏览；其余输出需要完整的文件树
+    let background = args.load.is_none()
+        && args.save.is_none()
+        && args.filter.is_none()
+        && !(args.find_empty
+            || args.zero_byte
+            || args.duplicates
+            || args.by_dir.is_some()
+            || args.deepest.is_some()
+            || args.summary
+            || args.by_ext
+            || args.by_owner
+            || args.anomalies
+            || args.json
+            || args.export_ncdu.is_some()
+            || args.csv.is_some()
+            || args.print);
+    let mut app = match &args.load {
+        Some(_) if !args.paths.is_empty() => bail!("A path cannot be combined with --load"),
+        Some(_) if args.skip_hidden => bail!("--skip-hidden cannot be combined with --load"),
+        Some(_) if args.watch.is_some() => bail!("--watch cannot be combined with --load"),
+        Some(_) if !args.excludes.is_empty() => bail!("--exclude cannot be combined with --load"),
+        Some(_) if args.max_children.is_some() => {
+            bail!("--max-children cannot be combined with --load")
+        }
+        Some(_) if args.include_dir_size => {
+            bail!("--include-dir-size cannot be combined with --load")
+        }
+        Some(_) if args.time_budget.is_some() => {
+            bail!("--time-budget cannot be combined with --load")
+        }
+        Some(_) if args.xattrs => bail!("--xattrs cannot be combined with --load"),
+        Some(cache) => load_cache(cache)?,
+        None => {
+            let options = ScanOptions {
+                group_dirs_first,
+                ..args.scan_options()
+            };
+            let paths = if args.paths.is_empty() {
+                vec![env::current_dir()?]
+            } else {
+                args.paths
+            };
+            if background {
+                App::scan_in_background(paths, options)?
+            } else {
+                // 再次保存到同一缓存时，以上次的条目数估计进度
+                let estimate = match (&args.save, paths.as_slice()) {
+                    (Some(cache), [root]) => cached_item_count(cache, root),
+                    _ => None,
+                };
+                let progress = (!args.quiet).then(|| ProgressDisplay::new(size_format, estimate));
+                scan(paths, options, progress)?
+            }
+        }
+    };
+    if let Some(path) = &args.save {
+        let file =
+            File::create(path).with_context(|| format!("Cannot create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        app.node.save_cache(&mut writer)?;
+        writer.flush()?;
+    }
+    // 在过滤之前查找，避免被过滤掉内容的目录被误报为空
+    if args.find_empty {
+        for path in app.node.empty_directories() {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
+    if args.zero_byte {
+        for path in app.node.zero_byte_files() {
+            println!("{}", path.display());
+        }
+        return Ok(());
+    }
+    match args.watch {
+        Some(0) => bail!("--watch interval must be at least 1 second"),
+        // 重新扫描得到的是未过滤的树
+        Some(_) if args.filter.is_some() => bail!("--watch cannot be combined with --filter"),
+        Some(secs) => app.watch = Some(Duration::from_secs(secs)),
+        None => {}
+    }
+    if let Some(pattern) = &args.filter {
+        app.node.filter_by_glob(pattern, args.keep_dir_sizes);
+    }
+    // 非交互输出默认完全展开
+    app.view.expand_depth = match args.expand_depth {
+        Some(depth) => depth,
+        None if args.print => usize::MAX,
+        None => 0,
+    };
+    app.view.sort = preferences.sort;
+    app.view.group_dirs_first = group_dirs_first;
+    app.view.column = preferences.column;
+    app.view.color = preferences.color && !args.no_color;
+    app.view.show_hidden = preferences.show_hidden && !args.no_hidden;
+    app.view.dirs_only = args.dirs_only;
+    app.keys = keys;
+    app.preferences_path = preferences_path;
+    app.view.size_format = size_format;
+    if let Some(threshold) = args.large_file_threshold {
+        app.view.large_file_threshold = threshold;
+    }
+    if let Some(min_size) = args.min_size {
+        app.view.min_size = min_size;
+    }
+    match &app.stats {
+        // 后台扫描的统计在退出界面后输出
+        _ if background => {}
+        Some(stats) => print_stats(stats, app.view.size_format),
+        // 从缓存加载时没有扫描统计，从文件树中查找
+        None => {
+            eprintln!("Max depth: {}", app.node.max_depth());
+            if let Some(file) = app.node.largest_file() {
+                eprintln!(
+                    "Largest file: {} ({})",
+                    file.path().display(),
+                    format_size(file.size(), app.view.size_format)
+                );
+            }
+        }
+    }
+    if let Some(summary) = app.skipped_summary() {
+        eprintln!("⚠️ {summary}");
+    }
+    if args.duplicates {
+        print_duplicates(&app.node, size_format);
+        return Ok(());
+    }
+    if let Some(n) = args.by_dir {
+        print_directories(&app.node, n, size_format);
+        return Ok(());
+    }
+    if args.summary {
+        print_summary(&app.node, size_format);
+        return Ok(());
+    }
+    if let Some(n) = args.deepest {
+        print_deepest(&app.node, n, size_format);
+        return Ok(());
+    }
+    if args.by_ext {
+        print_extensions(&app.node, size_format);
+        return Ok(());
+    }
+    if args.by_owner {
+        print_owners(&app.node, size_format);
+        return Ok(());
+    }
+    if args.anomalies {
+        print_anomalies(&app.node, size_format);
+        return Ok(());
+    }
+    if args.json {
+        println!("{}", serde_json::to_string_pretty(&app.node.to_json())?);
+        return Ok(());
+    }
+    if let Some(path) = args.export_ncdu {
+        let file =
+            File::create(&path).with_context(|| format!("Cannot create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        app.node.export_ncdu(&mut writer)?;
+        writer.flush()?;
+        return Ok(());
+    }
+    if let Some(path) = args.csv {
+        let file =
+            File::create(&path).with_context(|| format!("Cannot create {}", path.display()))?;
+        let mut writer = BufWriter::new(file);
+        app.node.write_csv(&mut writer)?;
+        writer.flush()?;
+        return Ok(());
+    }
+    if args.print {
+        app.print()?;
+        return Ok(());
+    }
     app.run()?;
+    if let Some(stats) = &app.stats
+        && background
+    {
+        print_stats(stats, app.view.size_format);
+        if let Some(summary) = app.skipped_summary() {
+            eprintln!("⚠️ {summary}");
+        }
+    }
     Ok(())
 }
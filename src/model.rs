@@ -1,23 +1,129 @@
 //! 文件系统数据模型
 //!
-//! 本模块提供了 [`Node`] 结构体，用于递归表示文件和目录信息，并提供 [`Node::scan`] 方法从实际文件系统构建树形结构。
+//! 本模块提供了 [`Node`] 结构体，用于递归表示文件和目录信息，并提供 [`Node::scan`] 方法从实际文件系统构建树形结构；
+//! [`Node::scan_with_fs`] 可从任意 [`FsProvider`]（如内存中的 [`MemoryFs`](crate::vfs::MemoryFs)）构建。
 
+use crate::error::FswhyError;
+use crate::ignore::{IgnoreStack, glob_match};
 use crate::model::NodeKind::*;
+use crate::vfs::{FsEntry, FsFileType, FsMetadata, FsProvider, StdFs};
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
 use std::path::{Path, PathBuf};
-use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Instant;
+use std::sync::atomic::{AtomicBool, AtomicU64, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant, SystemTime};
 
-#[derive(PartialOrd, PartialEq, Debug)]
+#[derive(Clone, PartialOrd, PartialEq, Debug, Serialize, Deserialize)]
 pub struct Node {
     path: PathBuf,
     size: u64,
     kind: NodeKind,
+    link: Option<HardLink>,
+    mtime: Option<SystemTime>,
+    #[serde(default)]
+    owner: Option<Ownership>,
 }
 
-#[derive(PartialOrd, PartialEq, Debug)]
+/// 硬链接信息，仅在开启去重时为链接数大于 1 的文件记录
+#[derive(Clone, Copy, PartialOrd, PartialEq, Debug, Serialize, Deserialize)]
+pub struct HardLink {
+    dev: u64,
+    ino: u64,
+    nlink: u64,
+    duplicate: bool,
+}
+
+impl HardLink {
+    /// 从元数据读取硬链接信息，链接数为 1 或没有 inode 信息（非 Unix 平台）时返回 `None`
+    fn from_metadata(meta: &FsMetadata) -> Option<Self> {
+        let (dev, ino) = meta.inode?;
+        (meta.nlink > 1).then_some(Self {
+            dev,
+            ino,
+            nlink: meta.nlink,
+            duplicate: false,
+        })
+    }
+
+    /// 设备号与 inode 号，唯一标识链接指向的文件
+    pub fn inode(&self) -> (u64, u64) {
+        (self.dev, self.ino)
+    }
+
+    /// 文件系统中指向该 inode 的链接总数
+    pub fn nlink(&self) -> u64 {
+        self.nlink
+    }
+
+    /// 是否为本次扫描中重复出现的 inode（不计入大小）
+    pub fn is_duplicate(&self) -> bool {
+        self.duplicate
+    }
+}
+
+/// 所有者与权限，仅在 Unix 平台上记录
+#[derive(Clone, Copy, PartialOrd, PartialEq, Debug, Serialize, Deserialize)]
+pub struct Ownership {
+    uid: u32,
+    gid: u32,
+    mode: u32,
+}
+
+impl Ownership {
+    /// 从元数据读取所有者与权限，平台不支持时返回 `None`
+    fn from_metadata(meta: &FsMetadata) -> Option<Self> {
+        let (uid, gid) = meta.owner?;
+        Some(Self {
+            uid,
+            gid,
+            mode: meta.mode?,
+        })
+    }
+
+    /// 所有者的用户 ID
+    pub fn uid(&self) -> u32 {
+        self.uid
+    }
+
+    /// 所属组 ID
+    pub fn gid(&self) -> u32 {
+        self.gid
+    }
+
+    /// 权限位（含文件类型位）
+    pub fn mode(&self) -> u32 {
+        self.mode
+    }
+
+    /// 其他用户是否可读
+    pub fn is_world_readable(&self) -> bool {
+        self.mode & 0o004 != 0
+    }
+
+    /// 权限位的 `ls -l` 形式，如 `rwxr-xr-x`（不含文件类型与特殊位）
+    pub fn permissions(&self) -> String {
+        (0..9)
+            .map(|i| {
+                if self.mode & (0o400 >> i) == 0 {
+                    '-'
+                } else {
+                    ['r', 'w', 'x'][i % 3]
+                }
+            })
+            .collect()
+    }
+}
+
+#[derive(Clone, PartialOrd, PartialEq, Debug, Serialize, Deserialize)]
 pub enum NodeKind {
     File,
     Directory(DirProperty),
+    /// 未跟随的符号链接（或跟随时指回祖先目录的链接），大小为链接本身的大小
+    Symlink,
+    /// 超出 [`ScanOptions::max_children_per_dir`] 而合并的条目，不对应实际路径，大小为被合并条目的总和
+    Omitted(OmittedEntries),
 }
 
 impl NodeKind {
@@ -26,15 +132,264 @@ impl NodeKind {
     }
 }
 
-#[derive(PartialOrd, PartialEq, Debug)]
+#[derive(Clone, PartialOrd, PartialEq, Debug, Serialize, Deserialize)]
 pub struct DirProperty {
     children: Vec<Node>,
+    truncated: bool,
+    file_count: u64,
+    #[serde(default)]
+    dir_count: u64,
+    /// 该目录自身有条目因错误被跳过
+    #[serde(default)]
+    skipped: bool,
+    /// 子树中有条目因错误被跳过（含自身）
+    #[serde(default)]
+    partial: bool,
+    /// 是否为其他文件系统的挂载点（开启 [`ScanOptions::same_filesystem`] 时不进入）
+    #[serde(default)]
+    other_filesystem: bool,
+    /// 目录条目自身占用的空间，已计入目录大小（开启 [`ScanOptions::include_dir_size`] 时）
+    #[serde(default)]
+    own_size: u64,
 }
 
 impl DirProperty {
     pub fn children(&self) -> &[Node] {
         &self.children
     }
+
+    /// 子节点是否因超出最大深度而未保留（此时 `children` 为空，但目录大小仍然准确）
+    ///
+    /// 其他文件系统的挂载点同样未保留子节点，但大小记为 0，见 [`is_other_filesystem`](Self::is_other_filesystem)。
+    pub fn is_truncated(&self) -> bool {
+        self.truncated
+    }
+
+    /// 是否为未进入的其他文件系统挂载点，其内容不计入大小
+    pub fn is_other_filesystem(&self) -> bool {
+        self.other_filesystem
+    }
+
+    /// 所有后代中的文件数（不含目录与符号链接）
+    pub fn file_count(&self) -> u64 {
+        self.file_count
+    }
+
+    /// 所有后代中的目录数（不含自身）
+    pub fn dir_count(&self) -> u64 {
+        self.dir_count
+    }
+
+    /// 子树中是否有条目因错误（如权限不足）被跳过，此时目录大小与计数只是下限
+    pub fn size_is_partial(&self) -> bool {
+        self.partial
+    }
+
+    /// 目录条目自身占用的空间，未开启 [`ScanOptions::include_dir_size`] 时为 0
+    pub fn own_size(&self) -> u64 {
+        self.own_size
+    }
+
+    /// 直接子目录数
+    pub fn child_dir_count(&self) -> usize {
+        self.children.iter().filter(|c| c.kind.is_dir()).count()
+    }
+}
+
+/// 合并到一个节点中的目录条目
+#[derive(Clone, Copy, Default, PartialOrd, PartialEq, Debug, Serialize, Deserialize)]
+pub struct OmittedEntries {
+    count: u64,
+    file_count: u64,
+    dir_count: u64,
+    partial: bool,
+}
+
+impl OmittedEntries {
+    /// 被合并的直接子条目数
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// 被合并的条目中（含其后代）的文件数
+    pub fn file_count(&self) -> u64 {
+        self.file_count
+    }
+
+    /// 被合并的条目中（含其后代）的目录数
+    pub fn dir_count(&self) -> u64 {
+        self.dir_count
+    }
+}
+
+/// 大小统计方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum SizeMode {
+    /// 表观大小，即文件长度
+    #[default]
+    Apparent,
+    /// 实际分配的磁盘空间（Unix 上为 `blocks * 512`，与 `du` 一致；其他平台回退为表观大小）
+    Allocated,
+}
+
+impl SizeMode {
+    /// 按当前方式从元数据中取大小
+    pub(crate) fn size_of(self, meta: &FsMetadata) -> u64 {
+        match self {
+            SizeMode::Apparent => meta.len,
+            SizeMode::Allocated => meta.allocated.unwrap_or(meta.len),
+        }
+    }
+}
+
+/// 合成根目录的占位路径，见 [`Node::multi_root`]
+pub const MULTI_ROOT_PATH: &str = "[multiple roots]";
+
+/// 同类型条目的排序方式
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortOrder {
+    /// 按名称升序（不区分大小写）
+    #[default]
+    NameAsc,
+    /// 按大小降序
+    SizeDesc,
+    /// 按大小升序
+    SizeAsc,
+}
+
+impl SortOrder {
+    /// 比较两个节点，见 [`compare_nodes`]
+    pub fn compare(self, a: &Node, b: &Node, group_dirs_first: bool) -> std::cmp::Ordering {
+        compare_nodes(a, b, self, group_dirs_first)
+    }
+}
+
+/// 比较两个节点：`group_dirs_first` 时目录优先于文件，同类型按排序方式比较；否则不区分类型，
+/// 只按排序方式比较。最后按名称保证结果确定
+///
+/// 名称比较不区分大小写（`apple` 在 `Zebra` 之前），相同时再按原始路径比较。
+pub fn compare_nodes(
+    a: &Node,
+    b: &Node,
+    order: SortOrder,
+    group_dirs_first: bool,
+) -> std::cmp::Ordering {
+    match (a.kind.is_dir(), b.kind.is_dir()) {
+        (true, false) if group_dirs_first => std::cmp::Ordering::Less,
+        (false, true) if group_dirs_first => std::cmp::Ordering::Greater,
+        _ => match order {
+            SortOrder::NameAsc => compare_names(a, b),
+            SortOrder::SizeDesc => b.size.cmp(&a.size).then_with(|| compare_names(a, b)),
+            SortOrder::SizeAsc => a.size.cmp(&b.size).then_with(|| compare_names(a, b)),
+        },
+    }
+}
+
+/// 按文件名（不区分大小写）比较，相同时按原始路径比较
+fn compare_names(a: &Node, b: &Node) -> std::cmp::Ordering {
+    let lower = |node: &Node| {
+        node.path
+            .file_name()
+            .unwrap_or(node.path.as_os_str())
+            .to_string_lossy()
+            .to_lowercase()
+    };
+    lower(a).cmp(&lower(b)).then_with(|| a.path.cmp(&b.path))
+}
+
+/// 扫描进度的报告频率
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum ProgressInterval {
+    /// 每扫描指定数量的条目报告一次（`0` 按 `1` 处理）
+    Items(usize),
+    /// 两次报告之间至少间隔指定时间，输出频率与扫描速度无关
+    Time(Duration),
+}
+
+impl Default for ProgressInterval {
+    fn default() -> Self {
+        ProgressInterval::Time(Duration::from_millis(100))
+    }
+}
+
+/// 扫描选项
+#[derive(Clone, Debug)]
+pub struct ScanOptions {
+    /// 最多使用的线程数（`0` 按 `1` 处理），默认为 CPU 核心数
+    pub threads: usize,
+    /// 是否遵循扫描范围内（及所在仓库上层）的 `.gitignore`
+    pub respect_gitignore: bool,
+    /// 额外的忽略模式，使用 `.gitignore` 语法，相对于扫描根目录
+    ///
+    /// 匹配的目录不进入、条目不计入大小；与 `.gitignore` 同时使用时任一规则忽略即跳过，`.gitignore` 中的 `!` 无法重新包含。
+    pub extra_ignores: Vec<String>,
+    /// 最大保留深度（根目录为 0），更深的目录只统计大小而不保留子节点
+    pub max_depth: Option<usize>,
+    /// 是否跟随符号链接；关闭时符号链接记录为 [`NodeKind::Symlink`]，开启时通过记录祖先目录打破循环
+    pub follow_symlinks: bool,
+    /// 文件大小的统计方式，目录大小为子节点按此方式统计的总和
+    pub size_mode: SizeMode,
+    /// 是否对硬链接去重：同一 inode 按树的顺序只有首次出现计入大小（仅 Unix；超出最大深度的子树内不去重）
+    pub dedup_hardlinks: bool,
+    /// 子节点的排序方式
+    pub sort_order: SortOrder,
+    /// 排序时是否把目录放在文件之前，默认为 `true`；关闭时目录与文件只按 [`sort_order`](Self::sort_order) 混合排序
+    pub group_dirs_first: bool,
+    /// 扫描进度（[`ScanProgress::Scanned`]）的报告频率
+    pub progress_interval: ProgressInterval,
+    /// 取消标志：被置为 `true` 后扫描尽快停止，并返回已扫描的部分文件树
+    pub cancel: Option<Arc<AtomicBool>>,
+    /// 报告 [`ScanProgress::DirFinished`] 的最大目录深度（根目录为 0），默认为 1；`0` 表示不报告
+    pub stats_depth: usize,
+    /// 是否只扫描根路径所在的文件系统（类似 `du -x`）：其他文件系统的挂载点记为空目录，不计入大小（仅 Unix）
+    pub same_filesystem: bool,
+    /// 是否跳过名称以 `.` 开头的条目（见 [`Node::is_hidden`]），跳过的条目不计入父目录大小；根路径本身总是扫描
+    pub skip_hidden: bool,
+    /// 每个目录最多保留的子节点数，`None` 表示不限
+    ///
+    /// 超出时只保留最大的若干个，其余合并为一个 [`NodeKind::Omitted`] 节点（名称如 `… (1234 more)`），
+    /// 目录的大小与文件数保持准确。用于避免条目极多的目录（如邮件队列、缓存目录）占用过多内存；
+    /// 被合并的文件不参与硬链接去重。
+    pub max_children_per_dir: Option<usize>,
+    /// 是否把目录条目自身占用的空间（按 [`size_mode`](Self::size_mode) 统计）计入目录大小，与 `du` 一致
+    ///
+    /// 关闭时（默认）目录大小只是其内容的总和。其他文件系统的挂载点仍记为 0。
+    pub include_dir_size: bool,
+    /// 扫描时间预算，`None` 表示不限
+    ///
+    /// 每进入一个目录时检查一次已用时间，超出后与 [`cancel`](Self::cancel) 被置位时一样停止扫描，
+    /// 返回已扫描的部分文件树，[`ScanReport::interrupted`] 为 `true`。
+    pub time_budget: Option<Duration>,
+    /// 是否把文件扩展属性（xattr）占用的字节数计入文件大小，见 [`FsProvider::xattr_size`]
+    ///
+    /// 计入的总量另见 [`ScanStats::xattr_bytes`]。需要启用 `xattr` 特性；读取扩展属性失败时按 0 计。
+    pub count_xattrs: bool,
+}
+
+impl Default for ScanOptions {
+    fn default() -> Self {
+        Self {
+            threads: thread::available_parallelism().map_or(1, |n| n.get()),
+            respect_gitignore: false,
+            extra_ignores: Vec::new(),
+            max_depth: None,
+            follow_symlinks: false,
+            size_mode: SizeMode::Apparent,
+            dedup_hardlinks: false,
+            sort_order: SortOrder::NameAsc,
+            group_dirs_first: true,
+            progress_interval: ProgressInterval::default(),
+            cancel: None,
+            skip_hidden: false,
+            stats_depth: 1,
+            same_filesystem: false,
+            max_children_per_dir: None,
+            include_dir_size: false,
+            time_budget: None,
+            count_xattrs: false,
+        }
+    }
 }
 
 impl Node {
@@ -42,121 +397,1710 @@ impl Node {
         &self.path
     }
 
+    /// 大小（字节）：文件为按 [`SizeMode`] 统计的大小，目录为子节点大小之和；
+    /// 开启 [`ScanOptions::include_dir_size`] 时目录还包括自身占用的空间（见 [`DirProperty::own_size`]）
+    ///
+    /// 目录的子树中有条目因错误被跳过时，该值只是下限，见 [`DirProperty::size_is_partial`]。
     pub fn size(&self) -> u64 {
         self.size
     }
 
+    /// 是否为大小只是下限的目录，见 [`DirProperty::size_is_partial`]
+    pub fn size_is_partial(&self) -> bool {
+        match &self.kind {
+            Directory(prop) => prop.partial,
+            Omitted(entries) => entries.partial,
+            File | Symlink => false,
+        }
+    }
+
+    /// 相对于 `root`（通常为扫描根目录的路径）的路径，用于在平铺的报告中区分同名条目
+    ///
+    /// 节点就是 `root` 时返回 `.`；不在 `root` 之下（如 [`Node::multi_root`] 合成根目录的子树）时返回完整路径。
+    pub fn relative_path(&self, root: &Path) -> &Path {
+        match self.path.strip_prefix(root) {
+            Ok(relative) if relative.as_os_str().is_empty() => Path::new("."),
+            Ok(relative) => relative,
+            Err(_) => &self.path,
+        }
+    }
+
+    /// 是否为隐藏条目，即文件名以 `.` 开头（如 `.git`）；路径 `.` 与 `..` 不算
+    pub fn is_hidden(&self) -> bool {
+        is_hidden_path(&self.path)
+    }
+
+    /// 是否为大小为 0 的文件（按扫描时统计的大小）；重复出现的硬链接虽记为 0 也不算
+    pub fn is_zero_byte_file(&self) -> bool {
+        matches!(self.kind, File) && self.size == 0 && !self.is_hardlink_duplicate()
+    }
+
     pub fn kind(&self) -> &NodeKind {
         &self.kind
     }
 
+    /// 硬链接信息，未开启去重或文件只有一个链接时为 `None`
+    pub fn hard_link(&self) -> Option<&HardLink> {
+        self.link.as_ref()
+    }
+
+    /// 所有后代中的文件数，对文件与符号链接为 0
+    pub fn descendant_file_count(&self) -> u64 {
+        match &self.kind {
+            Directory(prop) => prop.file_count,
+            File | Symlink | Omitted(_) => 0,
+        }
+    }
+
+    /// 作为子节点时对父目录文件数的贡献
+    fn file_count_contribution(&self) -> u64 {
+        match &self.kind {
+            Directory(prop) => prop.file_count,
+            File => 1,
+            Symlink => 0,
+            Omitted(entries) => entries.file_count,
+        }
+    }
+
+    /// 所有后代中的目录数，对文件与符号链接为 0
+    pub fn descendant_dir_count(&self) -> u64 {
+        match &self.kind {
+            Directory(prop) => prop.dir_count,
+            File | Symlink | Omitted(_) => 0,
+        }
+    }
+
+    /// 作为子节点时对父目录目录数的贡献
+    fn dir_count_contribution(&self) -> u64 {
+        match &self.kind {
+            Directory(prop) => prop.dir_count + 1,
+            Omitted(entries) => entries.dir_count,
+            File | Symlink => 0,
+        }
+    }
+
+    /// 修改时间，平台不支持时为 `None`
+    pub fn mtime(&self) -> Option<SystemTime> {
+        self.mtime
+    }
+
+    /// 所有者与权限，非 Unix 平台或无法读取元数据时为 `None`
+    pub fn owner(&self) -> Option<Ownership> {
+        self.owner
+    }
+
+    /// 自身及所有后代中最近的修改时间
+    ///
+    /// 对文件即为自身的修改时间；超出最大深度的目录只能反映其自身的修改时间，合并的条目为其中最近的修改时间。
+    pub fn latest_mtime(&self) -> Option<SystemTime> {
+        match &self.kind {
+            Directory(prop) => prop
+                .children
+                .iter()
+                .filter_map(Node::latest_mtime)
+                .chain(self.mtime)
+                .max(),
+            File | Symlink | Omitted(_) => self.mtime,
+        }
+    }
+
+    /// 按指定方式递归重新排序所有子节点，无需重新扫描；`group_dirs_first` 见 [`compare_nodes`]
+    pub fn sort(&mut self, order: SortOrder, group_dirs_first: bool) {
+        if let Directory(prop) = &mut self.kind {
+            prop.children
+                .sort_by(|a, b| order.compare(a, b, group_dirs_first));
+            for child in &mut prop.children {
+                child.sort(order, group_dirs_first);
+            }
+        }
+    }
+
+    /// 按先序惰性遍历子树中的所有节点（含自身），同时给出相对自身的深度（自身为 0）
+    ///
+    /// 按树中子节点的顺序遍历，与界面的展开状态无关；提前停止时不会访问剩余节点。
+    pub fn iter(&self) -> NodeIter<'_> {
+        NodeIter {
+            stack: vec![(self, 0)],
+        }
+    }
+
+    /// 只按 `order` 重排直接子节点，不递归；非目录不做任何事
+    pub fn sort_children(&mut self, order: SortOrder, group_dirs_first: bool) {
+        if let Directory(prop) = &mut self.kind {
+            prop.children
+                .sort_by(|a, b| order.compare(a, b, group_dirs_first));
+        }
+    }
+
+    /// 是否为重复的硬链接（大小记为 0）
+    pub fn is_hardlink_duplicate(&self) -> bool {
+        self.link.is_some_and(|link| link.duplicate)
+    }
+
     /// 递归扫描文件系统，构建节点树
     ///
     /// 此方法构建 [`Node`] 树。通过对子节点的大小求和来计算目录的总大小，并根据特定优先级对条目进行排序：
-    /// 1. 目录优先于文件。
-    /// 2. 同类型条目按路径字母顺序排序。
-    /// 扫描过程中会将进度信息显示到标准错误输出：
-    /// - 每扫描 100 项显示一次进度
-    /// - 顶层目录会显示详细统计信息
+    /// 1. 目录优先于文件（可通过 [`ScanOptions::group_dirs_first`] 关闭）。
+    /// 2. 同类型条目按名称字母顺序（不区分大小写）排序（可通过 [`ScanOptions::sort_order`] 修改）。
+    ///
+    /// 目录遍历会分发到与 CPU 核心数相同的线程上并行执行，见 [`scan_with_threads`](Self::scan_with_threads)。
+    ///
+    /// 此方法不输出进度，需要进度时使用 [`scan_with_progress`](Self::scan_with_progress)。
+    ///
+    /// # 错误
+    /// 路径不存在时返回 [`FswhyError::NotFound`]，权限不足以读取时返回 [`FswhyError::PermissionDenied`]，
+    /// 其他 IO 错误返回带路径的 [`FswhyError::Io`]。
+    pub fn scan(path: PathBuf) -> Result<Node, FswhyError> {
+        Self::scan_with_options(path, ScanOptions::default())
+    }
+
+    /// 使用至多 `threads` 个线程扫描文件系统
+    ///
+    /// 子目录会在有空闲线程时交给新线程扫描，否则在当前线程内继续递归，因此并发数不会超过 `threads`
+    /// （`0` 按 `1` 处理，即单线程扫描）。无论线程数多少，结果树的排序都与 [`scan`](Self::scan) 一致。
+    pub fn scan_with_threads(path: PathBuf, threads: usize) -> Result<Node, FswhyError> {
+        let options = ScanOptions {
+            threads,
+            ..ScanOptions::default()
+        };
+        Self::scan_with_options(path, options)
+    }
+
+    /// 按指定选项扫描文件系统
+    ///
+    /// 被忽略规则匹配的条目不会进入 `children`，也不计入父目录大小；被忽略的目录不会被遍历。
+    /// 根路径本身是符号链接时总是跟随。
+    pub fn scan_with_options(path: PathBuf, options: ScanOptions) -> Result<Node, FswhyError> {
+        Self::scan_with_progress(path, options, &mut |_| {}).map(|report| report.root)
+    }
+
+    /// 按指定选项扫描文件系统，并通过 `progress` 报告进度
+    ///
+    /// 回调可能在任意扫描线程中被调用（调用之间互斥），见 [`ScanProgress`]。
+    /// 根路径以下无法访问的条目会被跳过，并按路径排序后与文件树、统计信息一同返回。
+    ///
+    /// # 错误
+    /// 同 [`scan`](Self::scan)，只有根路径本身的错误会返回。
+    pub fn scan_with_progress(
+        path: PathBuf,
+        options: ScanOptions,
+        progress: &mut ProgressFn<'_>,
+    ) -> Result<ScanReport, FswhyError> {
+        Self::scan_with_fs(path, options, &StdFs, progress)
+    }
+
+    /// 同 [`scan_with_progress`](Self::scan_with_progress)，但通过 `fs` 访问文件系统
+    pub fn scan_with_fs(
+        path: PathBuf,
+        options: ScanOptions,
+        fs: &dyn FsProvider,
+        progress: &mut ProgressFn<'_>,
+    ) -> Result<ScanReport, FswhyError> {
+        let root = path.clone();
+        Self::scan_below(path, &root, 0, options, fs, progress)
+    }
+
+    /// 扫描扫描根目录 `root` 之下深度为 `depth` 的路径 `path`
+    ///
+    /// 额外的忽略模式相对于 `root` 匹配，最大深度按 `root` 计算；只扫描同一文件系统时以 `root` 所在设备为准。
+    fn scan_below(
+        path: PathBuf,
+        root: &Path,
+        depth: usize,
+        options: ScanOptions,
+        fs: &dyn FsProvider,
+        progress: &mut ProgressFn<'_>,
+    ) -> Result<ScanReport, FswhyError> {
+        let start = Instant::now();
+        let mut ctx = ScanContext {
+            excludes: IgnoreStack::push_patterns(None, root, &options.extra_ignores),
+            ignores: None,
+            ancestors: Vec::new(),
+            device: None,
+        };
+        if options.respect_gitignore {
+            ctx.ignores = IgnoreStack::push_ancestor_gitignores(ctx.ignores, &path);
+        }
+        if options.same_filesystem && depth > 0 {
+            ctx.device = fs.metadata(root).ok().as_ref().and_then(device_id);
+        }
+
+        progress(ScanProgress::Started { path: &path });
+        let scanner = Scanner::new(&options, fs, progress);
+
+        let result = thread::scope(|scope| scanner.scan_entry(scope, path, depth, &ctx));
+        let items = scanner.total_count.load(Ordering::Relaxed);
+        let interrupted = scanner.is_cancelled();
+        let progress = scanner
+            .progress
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner());
+        progress(ScanProgress::Finished { items });
+        let mut errors = scanner
+            .errors
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner());
+        errors.sort_by(|a, b| a.path.cmp(&b.path));
+
+        let mut root = result?;
+        if options.dedup_hardlinks {
+            root.mark_hardlink_duplicates(&mut HashSet::new());
+            // 去重改变了大小，按大小排序时需要重新排序
+            if options.sort_order != SortOrder::NameAsc {
+                root.sort(options.sort_order, options.group_dirs_first);
+            }
+        }
+        let stats = ScanStats {
+            files: scanner.files.into_inner(),
+            dirs: scanner.dirs.into_inner(),
+            bytes: root.size,
+            largest_file: scanner
+                .largest_file
+                .into_inner()
+                .unwrap_or_else(|e| e.into_inner()),
+            max_depth: scanner.max_depth.into_inner(),
+            elapsed: start.elapsed(),
+            xattr_bytes: scanner.xattr_bytes.into_inner(),
+        };
+        Ok(ScanReport {
+            root,
+            errors,
+            stats,
+            interrupted,
+        })
+    }
+
+    /// 按扫描整棵树时的 `options` 重新扫描树中路径为 `path` 的目录，替换其子节点并更新所有祖先目录的大小与文件数
+    ///
+    /// 忽略规则、最大深度与只扫描同一文件系统仍相对于 `path` 所在的扫描根目录；`options.cancel` 不生效，
+    /// 以免已被取消的扫描使重新扫描立即停止。
     ///
     /// # 错误
-    /// 如果路径不存在或权限不足以读取目录，则返回错误。
-    pub fn scan(path: PathBuf) -> anyhow::Result<Node> {
-        // 全局计数器，跨所有层级统计
-        static TOTAL_COUNT: AtomicUsize = AtomicUsize::new(0);
-        TOTAL_COUNT.store(0, Ordering::Relaxed);
+    /// 如果 `path` 不在树中、重新扫描失败或超出 [`ScanOptions::time_budget`]，则返回错误，原树保持不变。
+    pub fn rescan_subtree(&mut self, path: &Path, options: &ScanOptions) -> anyhow::Result<()> {
+        if !self.contains_path(path) {
+            anyhow::bail!("{} is not part of the tree", path.display());
+        }
+        // 合成根目录的占位路径不是扫描根目录，以所在的子树为准
+        let root = match &self.kind {
+            Directory(prop) if self.is_multi_root() => prop
+                .children
+                .iter()
+                .map(Node::path)
+                .find(|root| path.starts_with(root))
+                .unwrap_or(path),
+            _ => self.path(),
+        };
+        let depth = path
+            .strip_prefix(root)
+            .map_or(0, |relative| relative.components().count());
+        let options = ScanOptions {
+            cancel: None,
+            ..options.clone()
+        };
+        let report = Self::scan_below(
+            path.to_path_buf(),
+            root,
+            depth,
+            options,
+            &StdFs,
+            &mut |_| {},
+        )?;
+        if report.interrupted {
+            anyhow::bail!("Rescan of {} ran out of time", path.display());
+        }
+        self.replace_subtree(report.root);
+        Ok(())
+    }
+
+    /// 同 [`find`](Self::find)，返回可变引用
+    pub fn find_mut(&mut self, path: &Path) -> Option<&mut Node> {
+        if !self.is_multi_root() && !path.starts_with(&self.path) {
+            return None;
+        }
+        let mut node = self;
+        while node.path != path {
+            let Directory(prop) = &mut node.kind else {
+                return None;
+            };
+            node = prop
+                .children
+                .iter_mut()
+                .find(|child| path.starts_with(&child.path))?;
+        }
+        Some(node)
+    }
+
+    /// 子树中是否存在路径为 `path` 的节点
+    fn contains_path(&self, path: &Path) -> bool {
+        self.find(path).is_some()
+    }
+
+    /// 查找子树中路径为 `path` 的节点，不在子树中时返回 `None`
+    ///
+    /// 逐层进入路径是 `path` 前缀（按路径组成部分比较）的子节点，不遍历其他分支。
+    pub fn find(&self, path: &Path) -> Option<&Node> {
+        // 合成根目录的路径不是各子树的前缀
+        if !self.is_multi_root() && !path.starts_with(&self.path) {
+            return None;
+        }
+        let mut node = self;
+        while node.path != path {
+            let Directory(prop) = &node.kind else {
+                return None;
+            };
+            node = prop
+                .children
+                .iter()
+                .find(|child| path.starts_with(&child.path))?;
+        }
+        Some(node)
+    }
+
+    /// 用 `fresh` 替换路径相同的后代节点，并沿途重新计算目录大小与文件数
+    ///
+    /// 未找到对应节点时原样返回 `fresh`。
+    fn replace_subtree(&mut self, fresh: Node) -> Option<Node> {
+        if self.path == fresh.path {
+            *self = fresh;
+            return None;
+        }
+        // 合成根目录的路径不是各子树的前缀
+        if !self.is_multi_root() && !fresh.path.starts_with(&self.path) {
+            return Some(fresh);
+        }
+        let Directory(prop) = &mut self.kind else {
+            return Some(fresh);
+        };
+
+        let mut fresh = fresh;
+        for child in &mut prop.children {
+            match child.replace_subtree(fresh) {
+                Some(unused) => fresh = unused,
+                None => {
+                    self.recompute_totals();
+                    return None;
+                }
+            }
+        }
+        Some(fresh)
+    }
+
+    /// 将 `node` 插入树中父目录（按路径查找）的子节点，已有路径相同的子节点时替换，并沿途更新祖先目录的大小与文件数
+    ///
+    /// 用于把扫描完成的子树逐步加入 [`Node::empty_dir`] 等占位树；父目录不在树中时原样返回 `node`。
+    pub fn insert_descendant(&mut self, node: Node) -> Option<Node> {
+        let Some(parent) = node.path.parent() else {
+            return Some(node);
+        };
+        // 合成根目录的路径不是各子树的前缀
+        if !self.is_multi_root() && !parent.starts_with(&self.path) {
+            return Some(node);
+        }
+        let Directory(prop) = &mut self.kind else {
+            return Some(node);
+        };
+
+        if self.path == parent {
+            match prop.children.iter_mut().find(|c| c.path == node.path) {
+                Some(existing) => *existing = node,
+                None => prop.children.push(node),
+            }
+            self.recompute_totals();
+            return None;
+        }
+        let mut node = node;
+        for child in &mut prop.children {
+            match child.insert_descendant(node) {
+                Some(unused) => node = unused,
+                None => {
+                    self.recompute_totals();
+                    return None;
+                }
+            }
+        }
+        Some(node)
+    }
+
+    /// 从树中移除路径为 `path` 的后代节点并返回它，同时更新所有祖先目录的大小与文件数
+    ///
+    /// 只修改内存中的树，不涉及磁盘；根节点自身无法移除。
+    pub fn remove_descendant(&mut self, path: &Path) -> Option<Node> {
+        // 合成根目录的路径不是各子树的前缀
+        if (!self.is_multi_root() && !path.starts_with(&self.path)) || self.path == path {
+            return None;
+        }
+        let Directory(prop) = &mut self.kind else {
+            return None;
+        };
+
+        let removed = match prop.children.iter().position(|c| c.path == path) {
+            Some(index) => prop.children.remove(index),
+            None => prop
+                .children
+                .iter_mut()
+                .find_map(|child| child.remove_descendant(path))?,
+        };
+        self.recompute_totals();
+        Some(removed)
+    }
+
+    /// 将节点的路径改为 `path`，所有后代的路径随之改为位于 `path` 之下，大小与其他信息不变
+    ///
+    /// 用于在磁盘上移动条目后，把原来的子树插入新位置（见 [`insert_descendant`](Self::insert_descendant)），无需重新扫描。
+    pub fn relocate(&mut self, path: PathBuf) {
+        if let Directory(prop) = &mut self.kind {
+            for child in &mut prop.children {
+                let name = child.path.file_name().unwrap_or_default();
+                child.relocate(path.join(name));
+            }
+        }
+        self.path = path;
+    }
+
+    /// 自底向上根据子节点重新计算子树中所有目录的大小、文件数与是否不完整，返回节点的新大小
+    ///
+    /// 通过 [`find_mut`](Self::find_mut) 等直接修改子节点后调用，保证各级目录与子节点一致；
+    /// [`remove_descendant`](Self::remove_descendant) 已沿途更新祖先目录，无需再调用。
+    /// 未保留子节点的目录（超出最大深度或位于其他文件系统）保持原有大小。
+    pub fn recompute_sizes(&mut self) -> u64 {
+        if let Directory(prop) = &mut self.kind
+            && !prop.truncated
+        {
+            for child in &mut prop.children {
+                child.recompute_sizes();
+            }
+            self.recompute_totals();
+        }
+        self.size
+    }
 
-        eprintln!("Scanning {}...", path.display());
-        let result = Self::scan_with_progress(path, 0, &TOTAL_COUNT);
-        eprintln!();
-        result
+    /// 只保留文件名匹配 `pattern`（通配符，如 `*.log`）的文件与符号链接，以及包含匹配项的目录
+    ///
+    /// 根节点总是保留。`keep_dir_sizes` 为 `false` 时目录大小重新计算为保留文件的总和，
+    /// 否则保持扫描时的完整大小；目录文件数总是只统计保留的文件。
+    /// 未保留子节点的目录（超出最大深度）无法判断内容，一并移除。
+    pub fn filter_by_glob(&mut self, pattern: &str, keep_dir_sizes: bool) {
+        let pattern: Vec<char> = pattern.chars().collect();
+        self.retain_glob(&pattern, keep_dir_sizes);
     }
 
-    /// 带进度显示的递归扫描
+    /// 递归过滤子树，返回子树中是否存在匹配项
+    fn retain_glob(&mut self, pattern: &[char], keep_dir_sizes: bool) -> bool {
+        let Directory(prop) = &mut self.kind else {
+            let name: Vec<char> = self
+                .path
+                .file_name()
+                .unwrap_or_default()
+                .to_string_lossy()
+                .chars()
+                .collect();
+            return glob_match(pattern, &name);
+        };
+        prop.children
+            .retain_mut(|child| child.retain_glob(pattern, keep_dir_sizes));
+        let matched = !prop.children.is_empty();
+
+        let size = self.size;
+        self.recompute_totals();
+        if keep_dir_sizes {
+            self.size = size;
+        }
+        matched
+    }
+
+    /// 只计算路径的总大小，不构建节点也不排序，适合内存不足以容纳完整文件树的场景
     ///
-    /// 此方法由 [`scan`](Self::scan) 调用，递归构建目录树，同时更新全局原子计数器以显示进度。
+    /// 使用默认扫描选项，单线程执行；无法访问的子条目被跳过。
+    pub fn quick_size(path: &Path) -> Result<u64, FswhyError> {
+        Self::quick_size_with_options(path, &ScanOptions::default())
+    }
+
+    /// 同 [`quick_size`](Self::quick_size)，但遵循 `options` 中影响大小的选项
+    ///
+    /// 忽略规则、跳过隐藏条目、只扫描同一文件系统、大小统计方式、目录自身大小与扩展属性与 [`scan_with_options`](Self::scan_with_options)
+    /// 一致；不构建文件树，因此硬链接不去重，线程数、最大深度等只影响文件树的选项也被忽略。
+    /// 超出 [`ScanOptions::time_budget`] 或被取消时返回的是已统计部分的大小。
+    pub fn quick_size_with_options(path: &Path, options: &ScanOptions) -> Result<u64, FswhyError> {
+        let options = ScanOptions {
+            threads: 1,
+            ..options.clone()
+        };
+        let mut progress = |_: ScanProgress<'_>| {};
+        let scanner = Scanner::new(&options, &StdFs, &mut progress);
+        let meta = scanner
+            .metadata(path, 0)
+            .map_err(|e| FswhyError::from_io(path, e))?;
+        if !meta.is_dir() {
+            return Ok(scanner.file_size(path, &meta));
+        }
+        let mut ctx = ScanContext {
+            excludes: IgnoreStack::push_patterns(None, path, &options.extra_ignores),
+            ignores: None,
+            ancestors: Vec::new(),
+            device: None,
+        };
+        if options.respect_gitignore {
+            ctx.ignores = IgnoreStack::push_ancestor_gitignores(ctx.ignores, path);
+        }
+        // 根目录不会构成循环
+        let ctx = scanner.enter_dir(&ctx, path, &meta, 0).unwrap_or(ctx);
+        Ok(scanner.own_dir_size(&meta) + scanner.sum_size(path, 0, &ctx)?.size)
+    }
+
+    /// 没有子节点的目录节点，可作为扫描完成前的占位根节点
+    pub fn empty_dir(path: PathBuf) -> Node {
+        Node {
+            path,
+            size: 0,
+            kind: Directory(DirProperty {
+                children: Vec::new(),
+                truncated: false,
+                file_count: 0,
+                dir_count: 0,
+                skipped: false,
+                partial: false,
+                other_filesystem: false,
+                own_size: 0,
+            }),
+            link: None,
+            mtime: None,
+            owner: None,
+        }
+    }
+
+    /// 以多个文件树为子节点构造合成根目录，路径为 [`MULTI_ROOT_PATH`]，大小与计数为各子树之和
+    ///
+    /// 子节点保持给定的顺序。
+    pub fn multi_root(children: Vec<Node>) -> Node {
+        let mut root = Node {
+            path: PathBuf::from(MULTI_ROOT_PATH),
+            size: 0,
+            kind: Directory(DirProperty {
+                children,
+                truncated: false,
+                file_count: 0,
+                dir_count: 0,
+                skipped: false,
+                partial: false,
+                other_filesystem: false,
+                own_size: 0,
+            }),
+            link: None,
+            mtime: None,
+            owner: None,
+        };
+        root.recompute_totals();
+        root
+    }
+
+    /// 是否为 [`Node::multi_root`] 构造的合成根目录
+    pub fn is_multi_root(&self) -> bool {
+        self.path == Path::new(MULTI_ROOT_PATH)
+    }
+
+    /// 根据直接子节点重新计算目录的大小、文件数与是否不完整
+    fn recompute_totals(&mut self) {
+        if let Directory(prop) = &mut self.kind {
+            prop.partial = prop.skipped || prop.children.iter().any(Node::size_is_partial);
+            self.size = prop.own_size + prop.children.iter().map(|c| c.size).sum::<u64>();
+            prop.file_count = prop
+                .children
+                .iter()
+                .map(Node::file_count_contribution)
+                .sum();
+            prop.dir_count = prop.children.iter().map(Node::dir_count_contribution).sum();
+        }
+    }
+
+    /// 按树的顺序标记重复出现的硬链接并重新计算目录大小，返回节点的新大小
+    ///
+    /// 并行扫描中各线程的完成顺序不确定，因此去重放在排序完成之后进行，保证"首次出现"的判定可复现。
+    fn mark_hardlink_duplicates(&mut self, seen: &mut HashSet<(u64, u64)>) -> u64 {
+        match &mut self.kind {
+            Directory(prop) if !prop.truncated => {
+                self.size = prop.own_size
+                    + prop
+                        .children
+                        .iter_mut()
+                        .map(|child| child.mark_hardlink_duplicates(seen))
+                        .sum::<u64>();
+            }
+            _ => {
+                if let Some(link) = &mut self.link
+                    && !seen.insert((link.dev, link.ino))
+                {
+                    link.duplicate = true;
+                    self.size = 0;
+                }
+            }
+        }
+        self.size
+    }
+}
+
+/// [`Node::iter`] 返回的先序遍历迭代器
+pub struct NodeIter<'a> {
+    /// 待访问的节点及其深度，栈顶为下一个节点
+    stack: Vec<(&'a Node, usize)>,
+}
+
+impl<'a> Iterator for NodeIter<'a> {
+    type Item = (&'a Node, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (node, depth) = self.stack.pop()?;
+        if let Directory(prop) = &node.kind {
+            // 逆序入栈，使第一个子节点最先弹出
+            self.stack
+                .extend(prop.children.iter().rev().map(|child| (child, depth + 1)));
+        }
+        Some((node, depth))
+    }
+}
+
+/// 路径的文件名是否以 `.` 开头
+fn is_hidden_path(path: &Path) -> bool {
+    path.file_name()
+        .is_some_and(|name| name.to_string_lossy().starts_with('.'))
+}
+
+/// 条目所在设备号，非 Unix 平台返回 `None`（不区分文件系统）
+fn device_id(meta: &FsMetadata) -> Option<u64> {
+    meta.inode.map(|(dev, _)| dev)
+}
+
+/// 目录的唯一标识，用于检测符号链接循环
+#[cfg(unix)]
+type DirId = (u64, u64);
+#[cfg(not(unix))]
+type DirId = PathBuf;
+
+/// 获取目录标识：Unix 上为 (设备号, inode)，其他平台为规范化路径
+#[cfg(unix)]
+fn dir_id(_path: &Path, meta: &FsMetadata) -> Option<DirId> {
+    meta.inode
+}
+
+#[cfg(not(unix))]
+fn dir_id(path: &Path, _meta: &FsMetadata) -> Option<DirId> {
+    std::fs::canonicalize(path).ok()
+}
+
+/// 从上层目录继承的扫描上下文
+#[derive(Clone, Default)]
+struct ScanContext {
+    /// 额外的忽略模式（[`ScanOptions::extra_ignores`]），不受 `.gitignore` 影响
+    excludes: Option<Arc<IgnoreStack>>,
+    /// 生效的 `.gitignore` 规则
+    ignores: Option<Arc<IgnoreStack>>,
+    /// 祖先目录标识，仅在跟随符号链接时记录
+    ancestors: Vec<DirId>,
+    /// 根目录所在设备，仅在只扫描同一文件系统时记录
+    device: Option<u64>,
+}
+
+impl ScanContext {
+    /// 目录是否位于与根目录不同的文件系统上；未记录根目录设备时总为 `false`
+    fn is_other_filesystem(&self, meta: &FsMetadata) -> bool {
+        self.device
+            .is_some_and(|device| device_id(meta).is_some_and(|id| id != device))
+    }
+
+    fn is_ignored(&self, path: &Path, is_dir: bool) -> bool {
+        [&self.excludes, &self.ignores]
+            .into_iter()
+            .flatten()
+            .any(|stack| stack.is_ignored(path, is_dir))
+    }
+}
+
+/// 未保留子节点的子树的统计结果
+#[derive(Default)]
+struct Totals {
+    size: u64,
+    files: u64,
+    dirs: u64,
+    /// 是否有条目因错误被跳过
+    partial: bool,
+}
+
+/// 扫描进度事件
+#[derive(Clone, Copy, Debug)]
+pub enum ScanProgress<'a> {
+    /// 开始扫描根路径
+    Started { path: &'a Path },
+    /// 已扫描 `items` 项（频率见 [`ScanOptions::progress_interval`]），`path` 为刚完成的条目
+    ///
+    /// `bytes` 为已扫描文件的大小之和（硬链接去重之前），`elapsed` 为扫描开始至今的时间，可据此计算吞吐量。
+    Scanned {
+        items: usize,
+        bytes: u64,
+        elapsed: Duration,
+        path: &'a Path,
+    },
+    /// 深度不超过 [`ScanOptions::stats_depth`] 的目录扫描完成（该选项为 `0` 时不报告）
+    DirFinished {
+        path: &'a Path,
+        dirs: usize,
+        files: usize,
+        size: u64,
+        elapsed: Duration,
+    },
+    /// 根路径的直接子条目扫描完成，`node` 为其完整的子树，可据此在扫描结束前显示已完成的部分
+    SubtreeFinished { node: &'a Node },
+    /// 扫描结束，`items` 为总项数
+    Finished { items: usize },
+}
+
+/// 一次扫描的结果
+#[derive(Debug)]
+pub struct ScanReport {
+    /// 文件树根节点
+    pub root: Node,
+    /// 被跳过的条目，按路径排序
+    pub errors: Vec<ScanError>,
+    /// 整体统计
+    pub stats: ScanStats,
+    /// 扫描是否被取消或超出时间预算；此时文件树与统计只包含停止前已扫描的部分
+    pub interrupted: bool,
+}
+
+/// 整体扫描统计，包含超出最大深度、未保留子节点的子树
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct ScanStats {
+    /// 文件总数（不含符号链接）
+    pub files: u64,
+    /// 目录总数（含根目录）
+    pub dirs: u64,
+    /// 总大小（字节），与根节点大小一致
+    pub bytes: u64,
+    /// 最大的单个文件及其大小，大小相同时取路径较小者
+    pub largest_file: Option<(PathBuf, u64)>,
+    /// 目录的最大嵌套深度（根目录为 0），包含超出最大深度的子树
+    pub max_depth: usize,
+    /// 扫描耗时
+    pub elapsed: Duration,
+    /// 计入文件大小的扩展属性字节数，未开启 [`ScanOptions::count_xattrs`] 时为 0
+    pub xattr_bytes: u64,
+}
+
+impl ScanStats {
+    /// 合并另一次扫描的统计，耗时累加
+    pub fn merge(&mut self, other: ScanStats) {
+        self.files += other.files;
+        self.dirs += other.dirs;
+        self.bytes += other.bytes;
+        self.xattr_bytes += other.xattr_bytes;
+        self.elapsed += other.elapsed;
+        self.max_depth = self.max_depth.max(other.max_depth);
+        if let Some((path, size)) = other.largest_file {
+            let is_larger = self
+                .largest_file
+                .as_ref()
+                .is_none_or(|(p, s)| size > *s || (size == *s && path < *p));
+            if is_larger {
+                self.largest_file = Some((path, size));
+            }
+        }
+    }
+}
+
+/// 扫描中被跳过的条目
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ScanError {
+    /// 无法访问的路径（无法读取的目录条目记为其所在目录）
+    pub path: PathBuf,
+    /// 底层 IO 错误类型
+    pub kind: std::io::ErrorKind,
+    /// 被跳过的是否为目录，无法确定类型时为 `None`
+    pub is_dir: Option<bool>,
+}
+
+impl std::fmt::Display for ScanError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.path.display(), self.kind)
+    }
+}
+
+/// 只保留 `children` 中最大的 `max` 个（大小相同时按路径），其余合并到 `omitted` 中，返回其中的目录数
+fn omit_smallest(children: &mut Vec<Node>, max: usize, omitted: &mut Option<Node>) -> usize {
+    if children.len() <= max {
+        return 0;
+    }
+    if max > 0 {
+        children.select_nth_unstable_by(max - 1, |a, b| {
+            b.size.cmp(&a.size).then_with(|| a.path.cmp(&b.path))
+        });
+    }
+    let merged = omitted.get_or_insert_with(|| Node {
+        path: PathBuf::new(),
+        size: 0,
+        kind: Omitted(OmittedEntries::default()),
+        link: None,
+        mtime: None,
+        owner: None,
+    });
+    let mut dirs = 0;
+    for child in children.drain(max..) {
+        dirs += usize::from(child.kind.is_dir());
+        merged.size += child.size;
+        merged.mtime = merged.mtime.max(child.latest_mtime());
+        if let Omitted(entries) = &mut merged.kind {
+            entries.count += 1;
+            entries.file_count += child.file_count_contribution();
+            entries.dir_count += child.dir_count_contribution();
+            entries.partial |= child.size_is_partial();
+        }
+    }
+    dirs
+}
+
+/// 进度回调类型
+pub type ProgressFn<'a> = dyn FnMut(ScanProgress<'_>) + Send + 'a;
+
+/// 扫描过程中跨线程共享的状态
+struct Scanner<'a> {
+    options: &'a ScanOptions,
+    /// 文件系统访问接口
+    fs: &'a dyn FsProvider,
+    /// 用于跟踪扫描总项数的共享原子计数器
+    total_count: AtomicUsize,
+    /// 尚可派生的工作线程数
+    idle_threads: AtomicUsize,
+    /// 进度回调
+    progress: Mutex<&'a mut ProgressFn<'a>>,
+    /// 被跳过的条目
+    errors: Mutex<Vec<ScanError>>,
+    /// 已扫描的文件数
+    files: AtomicU64,
+    /// 已扫描文件的大小之和
+    bytes: AtomicU64,
+    /// 计入文件大小的扩展属性字节数
+    xattr_bytes: AtomicU64,
+    /// 已扫描的目录数
+    dirs: AtomicU64,
+    /// 目前最大的文件
+    largest_file: Mutex<Option<(PathBuf, u64)>>,
+    /// 已进入目录的最大深度
+    max_depth: AtomicUsize,
+    /// 扫描开始时间
+    start: Instant,
+    /// 上次报告进度的时间（相对 `start` 的纳秒数）
+    last_report_nanos: AtomicU64,
+    /// 是否已超出时间预算
+    out_of_time: AtomicBool,
+}
+
+impl<'a> Scanner<'a> {
+    fn new(
+        options: &'a ScanOptions,
+        fs: &'a dyn FsProvider,
+        progress: &'a mut ProgressFn<'a>,
+    ) -> Self {
+        Self {
+            options,
+            fs,
+            total_count: AtomicUsize::new(0),
+            idle_threads: AtomicUsize::new(options.threads.max(1) - 1),
+            progress: Mutex::new(progress),
+            errors: Mutex::new(Vec::new()),
+            files: AtomicU64::new(0),
+            bytes: AtomicU64::new(0),
+            xattr_bytes: AtomicU64::new(0),
+            dirs: AtomicU64::new(0),
+            largest_file: Mutex::new(None),
+            max_depth: AtomicUsize::new(0),
+            start: Instant::now(),
+            last_report_nanos: AtomicU64::new(0),
+            out_of_time: AtomicBool::new(false),
+        }
+    }
+}
+
+impl Scanner<'_> {
+    /// 尝试占用一个空闲线程名额
+    fn try_acquire_thread(&self) -> bool {
+        self.idle_threads
+            .fetch_update(Ordering::AcqRel, Ordering::Acquire, |n| n.checked_sub(1))
+            .is_ok()
+    }
+
+    /// 归还线程名额
+    fn release_thread(&self) {
+        self.idle_threads.fetch_add(1, Ordering::AcqRel);
+    }
+
+    /// 读取条目元数据
+    ///
+    /// 根路径总是跟随符号链接；其余符号链接仅在开启 `follow_symlinks` 时跟随，悬空链接按链接本身处理。
+    fn metadata(&self, path: &Path, depth: usize) -> std::io::Result<FsMetadata> {
+        let link_meta = self.fs.symlink_metadata(path)?;
+        if link_meta.is_symlink() && (depth == 0 || self.options.follow_symlinks) {
+            return Ok(self.fs.metadata(path).unwrap_or(link_meta));
+        }
+        Ok(link_meta)
+    }
+
+    /// 目录条目自身计入大小的部分，未开启 [`ScanOptions::include_dir_size`] 时为 0
+    fn own_dir_size(&self, meta: &FsMetadata) -> u64 {
+        if self.options.include_dir_size {
+            self.options.size_mode.size_of(meta)
+        } else {
+            0
+        }
+    }
+
+    /// 判断目录条目是否应作为目录对待（用于忽略规则匹配与线程分配）
+    fn entry_is_dir(&self, entry: &FsEntry) -> bool {
+        match entry.file_type {
+            Some(FsFileType::Symlink) => {
+                self.options.follow_symlinks
+                    && self.fs.metadata(&entry.path).is_ok_and(|m| m.is_dir())
+            }
+            Some(t) => t == FsFileType::Dir,
+            None => false,
+        }
+    }
+
+    /// 条目是否被忽略规则或 [`ScanOptions::skip_hidden`] 排除
+    fn is_excluded(&self, ctx: &ScanContext, path: &Path, is_dir: bool) -> bool {
+        (self.options.skip_hidden && is_hidden_path(path)) || ctx.is_ignored(path, is_dir)
+    }
+
+    /// 进入深度为 `depth` 的目录：叠加该目录的忽略规则并记录祖先，检测到循环时返回 `None`
+    ///
+    /// 构成循环的链接不进入，因而不计入最大深度。
+    fn enter_dir(
+        &self,
+        parent: &ScanContext,
+        path: &Path,
+        meta: &FsMetadata,
+        depth: usize,
+    ) -> Option<ScanContext> {
+        let mut ctx = parent.clone();
+        if self.options.follow_symlinks
+            && let Some(id) = dir_id(path, meta)
+        {
+            if ctx.ancestors.contains(&id) {
+                return None;
+            }
+            ctx.ancestors.push(id);
+        }
+        self.max_depth.fetch_max(depth, Ordering::Relaxed);
+        if self.options.respect_gitignore {
+            ctx.ignores = IgnoreStack::push_gitignore(ctx.ignores, path);
+        }
+        // 第一个进入的目录即根目录
+        if self.options.same_filesystem && ctx.device.is_none() {
+            ctx.device = device_id(meta);
+        }
+        Some(ctx)
+    }
+
+    /// 构建符号链接节点，大小为链接本身的大小
+    fn symlink_node(&self, path: PathBuf) -> Node {
+        let meta = self.fs.symlink_metadata(&path).ok();
+        Node {
+            size: meta
+                .as_ref()
+                .map_or(0, |m| self.options.size_mode.size_of(m)),
+            owner: meta.as_ref().and_then(Ownership::from_metadata),
+            mtime: meta.and_then(|m| m.modified),
+            path,
+            kind: Symlink,
+            link: None,
+        }
+    }
+
+    /// 读取目录条目，跳过并记录无法读取的条目；第二项表示是否有条目被跳过
+    fn read_entries(&self, path: &Path) -> Result<(Vec<FsEntry>, bool), FswhyError> {
+        let mut skipped = false;
+        let entries = self
+            .fs
+            .read_dir(path)
+            .map_err(|e| FswhyError::from_io(path, e))?
+            .into_iter()
+            .filter_map(|entry_result| {
+                entry_result
+                    .map_err(|e| {
+                        skipped = true;
+                        self.record_error(path, e.kind(), None);
+                    })
+                    .ok()
+            })
+            .collect();
+        Ok((entries, skipped))
+    }
+
+    /// 记录被跳过的条目
+    fn record_error(&self, path: &Path, kind: std::io::ErrorKind, is_dir: Option<bool>) {
+        let error = ScanError {
+            path: path.to_path_buf(),
+            kind,
+            is_dir,
+        };
+        self.errors
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .push(error);
+    }
+
+    /// 文件的大小，开启 [`ScanOptions::count_xattrs`] 时包括扩展属性
+    fn file_size(&self, path: &Path, meta: &FsMetadata) -> u64 {
+        let size = self.options.size_mode.size_of(meta);
+        if !self.options.count_xattrs {
+            return size;
+        }
+        let xattrs = self.fs.xattr_size(path).unwrap_or(0);
+        self.xattr_bytes.fetch_add(xattrs, Ordering::Relaxed);
+        size + xattrs
+    }
+
+    /// 统计一个文件，并更新最大文件
+    fn record_file(&self, path: &Path, size: u64) {
+        self.files.fetch_add(1, Ordering::Relaxed);
+        self.bytes.fetch_add(size, Ordering::Relaxed);
+        let mut largest = self.largest_file.lock().unwrap_or_else(|e| e.into_inner());
+        let is_larger = largest
+            .as_ref()
+            .is_none_or(|(p, s)| size > *s || (size == *s && path < p.as_path()));
+        if is_larger {
+            *largest = Some((path.to_path_buf(), size));
+        }
+    }
+
+    /// 带进度报告的递归扫描
+    ///
+    /// 此方法由 [`Node::scan_with_progress`] 调用，递归构建目录树，同时更新共享原子计数器以报告进度。
     ///
     /// # 参数
+    /// * `scope` - 用于派生子目录扫描线程的作用域
     /// * `path` - 要扫描的文件系统路径
     /// * `depth` - 当前递归深度（根目录为 0）
-    /// * `total_count` - 用于跟踪扫描总项数的共享原子计数器
+    /// * `ctx` - 从上层目录继承的忽略规则与祖先目录
     ///
-    /// # 进度显示
-    /// - 每扫描 100 项向标准错误输出显示一次进度
-    /// - 对于深度为 0 或 1 的目录，显示详细统计信息（目录/文件计数、大小、时间），以避免输出过多信息
+    /// # 进度报告
+    /// - 按 [`ScanOptions::progress_interval`] 报告 [`ScanProgress::Scanned`]
+    /// - 对于深度不超过 [`ScanOptions::stats_depth`] 的目录，报告 [`ScanProgress::DirFinished`]（目录/文件计数、大小、时间），以避免事件过多
     ///
     /// # 错误处理
-    /// - 跳过无法访问的条目，继续扫描
-    /// - 仅对顶层条目（深度 ≤ 1）记录错误到标准错误输出
-    fn scan_with_progress(
+    /// - 跳过根路径以下无法访问的条目，记录为 [`ScanError`] 后继续扫描
+    fn scan_entry<'scope, 'env>(
+        &'env self,
+        scope: &'scope thread::Scope<'scope, 'env>,
         path: PathBuf,
         depth: usize,
-        total_count: &AtomicUsize,
-    ) -> anyhow::Result<Node> {
+        ctx: &ScanContext,
+    ) -> Result<Node, FswhyError> {
         let start = Instant::now();
-        let meta = std::fs::metadata(&path)?;
+        let meta = self
+            .metadata(&path, depth)
+            .map_err(|e| FswhyError::from_io(&path, e))?;
+
+        if meta.is_symlink() {
+            return Ok(self.symlink_node(path));
+        }
 
         if meta.is_dir() {
-            let mut children: Vec<Node> = std::fs::read_dir(&path)?
-                .filter_map(|entry_result| {
-                    entry_result
-                        .map_err(|e| {
-                            if depth <= 1 {
-                                eprintln!("\n✗ Skipped reading a directory entry: {}", e);
-                            }
-                            e
-                        })
-                        .ok()
-                })
-                .map(|entry| {
-                    let child_path = entry.path();
-                    let child_node = Self::scan_with_progress(child_path, depth + 1, total_count)?;
-
-                    let count = total_count.fetch_add(1, Ordering::Relaxed) + 1;
-                    if count % 100 == 0 {
-                        eprint!("\rScanned {} items...", count);
-                        std::io::Write::flush(&mut std::io::stderr()).ok();
-                    }
+            // 其他文件系统的挂载点：保留为空目录，不进入也不计入大小
+            if ctx.is_other_filesystem(&meta) {
+                self.dirs.fetch_add(1, Ordering::Relaxed);
+                return Ok(Node {
+                    path,
+                    size: 0,
+                    kind: Directory(DirProperty {
+                        children: Vec::new(),
+                        truncated: true,
+                        file_count: 0,
+                        dir_count: 0,
+                        skipped: false,
+                        partial: false,
+                        other_filesystem: true,
+                        own_size: 0,
+                    }),
+                    link: None,
+                    mtime: meta.modified,
+                    owner: Ownership::from_metadata(&meta),
+                });
+            }
 
-                    Ok(child_node)
-                })
-                .collect::<anyhow::Result<Vec<Node>>>()?;
+            // 指向祖先目录的链接会形成循环，按链接本身记录
+            let Some(ctx) = self.enter_dir(ctx, &path, &meta, depth) else {
+                return Ok(self.symlink_node(path));
+            };
 
-            let dir_count = children.iter().filter(|c| c.kind.is_dir()).count();
-            let file_count = children.len() - dir_count;
+            // 超出最大深度：只统计大小，不保留子节点
+            if self.options.max_depth.is_some_and(|max| depth >= max) {
+                let totals = self.sum_size(&path, depth, &ctx)?;
+                self.dirs.fetch_add(1, Ordering::Relaxed);
+                let own_size = self.own_dir_size(&meta);
+                return Ok(Node {
+                    path,
+                    size: own_size + totals.size,
+                    kind: Directory(DirProperty {
+                        children: Vec::new(),
+                        truncated: true,
+                        file_count: totals.files,
+                        dir_count: totals.dirs,
+                        skipped: totals.partial,
+                        partial: totals.partial,
+                        other_filesystem: false,
+                        own_size,
+                    }),
+                    link: None,
+                    mtime: meta.modified,
+                    owner: Ownership::from_metadata(&meta),
+                });
+            }
 
-            // 目录优先，按路径排序
-            children.sort_by(|a, b| match (&a.kind, &b.kind) {
-                (Directory(_), File) => std::cmp::Ordering::Less,
-                (File, Directory(_)) => std::cmp::Ordering::Greater,
-                _ => a.path.cmp(&b.path),
-            });
+            self.check_time_budget();
+            let (entries, mut skipped) = self.read_entries(&path)?;
+            self.dirs.fetch_add(1, Ordering::Relaxed);
 
-            let total_size: u64 = children.iter().map(|c| c.size).sum();
+            // 有空闲线程时子目录交给新线程，其余条目在当前线程扫描
+            let max_children = self.options.max_children_per_dir;
+            let mut children: Vec<Node> = Vec::with_capacity(
+                entries
+                    .len()
+                    .min(max_children.map_or(usize::MAX, |n| 2 * n)),
+            );
+            let mut omitted = None;
+            let mut omitted_dirs = 0;
+            let mut handles = Vec::new();
+            for entry in entries {
+                if self.is_cancelled() {
+                    break;
+                }
+                let is_dir = self.entry_is_dir(&entry);
+                let child_path = entry.path;
+                if self.is_excluded(&ctx, &child_path, is_dir) {
+                    continue;
+                }
+                let known_dir = entry.file_type.map(|_| is_dir);
 
-            // 顶层目录打印统计
-            if depth <= 1 {
-                eprintln!(
-                    "\n✓ {} ({} dirs, {} files, {:.1} MB) in {:.2}s",
-                    path.display(),
-                    dir_count,
-                    file_count,
-                    total_size as f64 / 1024.0 / 1024.0,
-                    start.elapsed().as_secs_f64(),
-                );
+                if is_dir && self.try_acquire_thread() {
+                    let ctx = ctx.clone();
+                    handles.push(scope.spawn(move || {
+                        let result = self.scan_child(scope, child_path, known_dir, depth + 1, &ctx);
+                        self.release_thread();
+                        result
+                    }));
+                } else {
+                    match self.scan_child(scope, child_path, known_dir, depth + 1, &ctx) {
+                        Some(child_node) => children.push(child_node),
+                        None => skipped = true,
+                    }
+                    // 超出上限一倍时才裁剪，避免每个条目都重新选择
+                    if let Some(max) = max_children
+                        && children.len() >= 2 * max.max(1)
+                    {
+                        omitted_dirs += omit_smallest(&mut children, max, &mut omitted);
+                    }
+                }
+            }
+            for handle in handles {
+                let child_node = handle.join().map_err(|_| FswhyError::ThreadPanicked)?;
+                match child_node {
+                    Some(child_node) => children.push(child_node),
+                    None => skipped = true,
+                }
+            }
+            if let Some(max) = max_children {
+                omitted_dirs += omit_smallest(&mut children, max, &mut omitted);
+            }
+
+            // 直接子条目数包括被合并的条目
+            let mut dir_count = children.iter().filter(|c| c.kind.is_dir()).count();
+            let mut file_count = children.len() - dir_count;
+            if let Some(mut node) = omitted
+                && let Omitted(entries) = node.kind
+            {
+                dir_count += omitted_dirs;
+                file_count += entries.count as usize - omitted_dirs;
+                node.path = path.join(format!("… ({} more)", entries.count));
+                children.push(node);
+            }
+
+            // 按排序方式排序，默认目录优先（线程完成顺序不影响结果）
+            let order = self.options.sort_order;
+            let group_dirs_first = self.options.group_dirs_first;
+            children.sort_by(|a, b| order.compare(a, b, group_dirs_first));
+
+            let own_size = self.own_dir_size(&meta);
+            let total_size: u64 = own_size + children.iter().map(|c| c.size).sum::<u64>();
+            let descendant_files: u64 = children.iter().map(Node::file_count_contribution).sum();
+            let descendant_dirs: u64 = children.iter().map(Node::dir_count_contribution).sum();
+            let partial = skipped || children.iter().any(Node::size_is_partial);
+
+            // 较浅的目录报告统计
+            let max = self.options.stats_depth;
+            if max > 0 && depth <= max {
+                self.report(ScanProgress::DirFinished {
+                    path: &path,
+                    dirs: dir_count,
+                    files: file_count,
+                    size: total_size,
+                    elapsed: start.elapsed(),
+                });
             }
 
             Ok(Node {
                 path,
                 size: total_size,
-                kind: Directory(DirProperty { children }),
+                kind: Directory(DirProperty {
+                    children,
+                    truncated: false,
+                    file_count: descendant_files,
+                    dir_count: descendant_dirs,
+                    skipped,
+                    partial,
+                    other_filesystem: false,
+                    own_size,
+                }),
+                link: None,
+                mtime: meta.modified,
+                owner: Ownership::from_metadata(&meta),
             })
         } else {
+            let size = self.file_size(&path, &meta);
+            self.record_file(&path, size);
             Ok(Node {
                 path,
-                size: meta.len(),
+                size,
                 kind: File,
+                link: if self.options.dedup_hardlinks {
+                    HardLink::from_metadata(&meta)
+                } else {
+                    None
+                },
+                mtime: meta.modified,
+                owner: Ownership::from_metadata(&meta),
+            })
+        }
+    }
+
+    /// 递归累加目录大小与文件数，不构建节点
+    ///
+    /// 用于超出最大深度的子树，忽略规则、符号链接与错误处理与 [`scan_entry`](Self::scan_entry) 一致。
+    fn sum_size(&self, path: &Path, depth: usize, ctx: &ScanContext) -> Result<Totals, FswhyError> {
+        self.check_time_budget();
+        let (entries, skipped) = self.read_entries(path)?;
+        let mut totals = Totals {
+            partial: skipped,
+            ..Totals::default()
+        };
+        for entry in entries {
+            if self.is_cancelled() {
+                break;
+            }
+            let known_dir = entry.file_type.map(|_| self.entry_is_dir(&entry));
+            let child_path = entry.path;
+            let meta = match self.metadata(&child_path, depth + 1) {
+                Ok(meta) => meta,
+                Err(e) => {
+                    self.record_error(&child_path, e.kind(), known_dir);
+                    totals.partial = true;
+                    continue;
+                }
+            };
+            if self.is_excluded(ctx, &child_path, meta.is_dir()) {
+                continue;
+            }
+
+            if meta.is_dir() && ctx.is_other_filesystem(&meta) {
+                // 挂载点计为目录，但不计入大小
+                self.dirs.fetch_add(1, Ordering::Relaxed);
+                totals.dirs += 1;
+            } else if meta.is_dir() {
+                match self.enter_dir(ctx, &child_path, &meta, depth + 1) {
+                    Some(child_ctx) => match self.sum_size(&child_path, depth + 1, &child_ctx) {
+                        Ok(child) => {
+                            self.dirs.fetch_add(1, Ordering::Relaxed);
+                            totals.size += self.own_dir_size(&meta) + child.size;
+                            totals.files += child.files;
+                            totals.dirs += child.dirs + 1;
+                            totals.partial |= child.partial;
+                        }
+                        Err(e) => {
+                            self.record_error(&child_path, e.io_kind(), Some(true));
+                            totals.partial = true;
+                        }
+                    },
+                    None => totals.size += self.symlink_node(child_path.clone()).size,
+                }
+            } else if meta.is_symlink() {
+                totals.size += self.options.size_mode.size_of(&meta);
+            } else {
+                let size = self.file_size(&child_path, &meta);
+                totals.size += size;
+                totals.files += 1;
+                self.record_file(&child_path, size);
+            }
+            self.count_item(&child_path);
+        }
+        Ok(totals)
+    }
+
+    /// 扫描单个子条目并更新进度计数，无法访问时记录错误并跳过；`is_dir` 为条目是否为目录，类型未知时为 `None`
+    fn scan_child<'scope, 'env>(
+        &'env self,
+        scope: &'scope thread::Scope<'scope, 'env>,
+        path: PathBuf,
+        is_dir: Option<bool>,
+        depth: usize,
+        ctx: &ScanContext,
+    ) -> Option<Node> {
+        match self.scan_entry(scope, path.clone(), depth, ctx) {
+            Ok(child_node) => {
+                self.count_item(child_node.path());
+                if depth == 1 {
+                    self.report(ScanProgress::SubtreeFinished { node: &child_node });
+                }
+                Some(child_node)
+            }
+            Err(e) => {
+                self.record_error(&path, e.io_kind(), is_dir);
+                None
+            }
+        }
+    }
+
+    /// 计数一项，并按 [`ScanOptions::progress_interval`] 报告进度：按项数时每满 `n` 项报告一次，
+    /// 按时间时（默认）距上次报告至少经过指定间隔才报告
+    fn count_item(&self, path: &Path) {
+        let count = self.total_count.fetch_add(1, Ordering::Relaxed) + 1;
+        let due = match self.options.progress_interval {
+            ProgressInterval::Items(n) => count.is_multiple_of(n.max(1)),
+            ProgressInterval::Time(interval) => {
+                // 只有成功更新上次报告时间的线程负责报告
+                let now = self.start.elapsed().as_nanos() as u64;
+                let last = self.last_report_nanos.load(Ordering::Relaxed);
+                now.saturating_sub(last) >= interval.as_nanos() as u64
+                    && self
+                        .last_report_nanos
+                        .compare_exchange(last, now, Ordering::Relaxed, Ordering::Relaxed)
+                        .is_ok()
+            }
+        };
+        if due {
+            self.report(ScanProgress::Scanned {
+                items: count,
+                bytes: self.bytes.load(Ordering::Relaxed),
+                elapsed: self.start.elapsed(),
+                path,
+            });
+        }
+    }
+
+    /// 扫描是否已被取消或超出时间预算
+    fn is_cancelled(&self) -> bool {
+        self.out_of_time.load(Ordering::Relaxed)
+            || self
+                .options
+                .cancel
+                .as_ref()
+                .is_some_and(|cancel| cancel.load(Ordering::Relaxed))
+    }
+
+    /// 已用时间超出预算时标记为超时，之后 [`is_cancelled`](Self::is_cancelled) 返回 `true`
+    fn check_time_budget(&self) {
+        if let Some(budget) = self.options.time_budget
+            && self.start.elapsed() > budget
+        {
+            self.out_of_time.store(true, Ordering::Relaxed);
+        }
+    }
+
+    /// 调用进度回调
+    fn report(&self, event: ScanProgress<'_>) {
+        let mut progress = self.progress.lock().unwrap_or_else(|e| e.into_inner());
+        progress(event);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::{MEMORY_DIR_LEN, MemoryFs};
+
+    /// 在虚拟树上扫描 `root`
+    fn scan_memory(fs: &MemoryFs, root: &str, options: ScanOptions) -> ScanReport {
+        Node::scan_with_fs(PathBuf::from(root), options, fs, &mut |_| {}).unwrap()
+    }
+
+    fn node<'a>(root: &'a Node, path: &str) -> &'a Node {
+        root.find(Path::new(path))
+            .unwrap_or_else(|| panic!("{path} is not in the tree"))
+    }
+
+    /// 子节点的名称，按树中的顺序
+    fn child_names(node: &Node) -> Vec<String> {
+        let Directory(prop) = node.kind() else {
+            return Vec::new();
+        };
+        prop.children()
+            .iter()
+            .map(|child| {
+                child
+                    .path
+                    .file_name()
+                    .unwrap()
+                    .to_string_lossy()
+                    .into_owned()
             })
+            .collect()
+    }
+
+    fn file_node(path: &str, size: u64) -> Node {
+        Node {
+            path: PathBuf::from(path),
+            size,
+            kind: File,
+            link: None,
+            mtime: None,
+            owner: None,
         }
     }
+
+    #[test]
+    fn compare_nodes_ordering() {
+        use std::cmp::Ordering::*;
+        let dir = Node::empty_dir(PathBuf::from("/r/zzz"));
+        let apple = file_node("/r/apple", 10);
+        let zebra = file_node("/r/Zebra", 10);
+        let big = file_node("/r/big", 99);
+
+        // 目录优先；关闭时目录与文件只按排序方式比较
+        assert_eq!(compare_nodes(&dir, &apple, SortOrder::NameAsc, true), Less);
+        assert_eq!(
+            compare_nodes(&dir, &apple, SortOrder::NameAsc, false),
+            Greater
+        );
+        // 名称不区分大小写
+        assert_eq!(
+            compare_nodes(&apple, &zebra, SortOrder::NameAsc, true),
+            Less
+        );
+        // 按大小排序，大小相同时按名称
+        assert_eq!(compare_nodes(&big, &apple, SortOrder::SizeDesc, true), Less);
+        assert_eq!(
+            compare_nodes(&big, &apple, SortOrder::SizeAsc, true),
+            Greater
+        );
+        assert_eq!(
+            compare_nodes(&apple, &zebra, SortOrder::SizeDesc, true),
+            Less
+        );
+        // 只有大小写不同的名称按原始路径区分，结果确定
+        let upper = file_node("/r/APPLE", 10);
+        assert_eq!(
+            compare_nodes(&upper, &apple, SortOrder::NameAsc, true),
+            Less
+        );
+        assert_eq!(
+            compare_nodes(&apple, &apple, SortOrder::NameAsc, true),
+            Equal
+        );
+    }
+
+    #[test]
+    fn scan_sorts_children() {
+        let fs = MemoryFs::new()
+            .file("/r/Zebra", 1)
+            .file("/r/apple", 30)
+            .file("/r/beta/x", 5)
+            .file("/r/Alpha/y", 20);
+        let root = scan_memory(&fs, "/r", ScanOptions::default()).root;
+        assert_eq!(child_names(&root), ["Alpha", "beta", "apple", "Zebra"]);
+
+        let options = ScanOptions {
+            sort_order: SortOrder::SizeDesc,
+            group_dirs_first: false,
+            ..ScanOptions::default()
+        };
+        let root = scan_memory(&fs, "/r", options).root;
+        assert_eq!(child_names(&root), ["apple", "Alpha", "beta", "Zebra"]);
+
+        // 线程数不影响结果
+        for threads in [1, 4] {
+            let options = ScanOptions {
+                threads,
+                ..ScanOptions::default()
+            };
+            let root = scan_memory(&fs, "/r", options).root;
+            assert_eq!(child_names(&root), ["Alpha", "beta", "apple", "Zebra"]);
+        }
+    }
+
+    #[test]
+    fn scan_sums_sizes_and_counts() {
+        let fs = MemoryFs::new()
+            .file("/r/a", 100)
+            .file("/r/d1/b", 20)
+            .file("/r/d1/d2/c", 3)
+            .dir("/r/empty");
+        let report = scan_memory(&fs, "/r", ScanOptions::default());
+        let root = &report.root;
+        assert_eq!(root.size(), 123);
+        assert_eq!(node(root, "/r/d1").size(), 23);
+        assert_eq!(node(root, "/r/d1/d2").size(), 3);
+        assert_eq!(node(root, "/r/empty").size(), 0);
+        assert_eq!(root.descendant_file_count(), 3);
+        assert_eq!(root.descendant_dir_count(), 3);
+        assert_eq!(report.stats.files, 3);
+        assert_eq!(report.stats.dirs, 4);
+        assert_eq!(report.stats.bytes, 123);
+        assert!(report.errors.is_empty());
+        assert!(!report.interrupted);
+    }
+
+    #[test]
+    fn scan_skips_unreadable_entries() {
+        let fs = MemoryFs::new()
+            .file("/r/ok", 10)
+            .file("/r/sub/ok", 5)
+            .error("/r/sub/locked", std::io::ErrorKind::PermissionDenied);
+        let report = scan_memory(&fs, "/r", ScanOptions::default());
+        let root = &report.root;
+
+        // 无法访问的条目被跳过并记录，所在目录及其上层的大小只是下限
+        assert_eq!(root.size(), 15);
+        assert_eq!(child_names(node(root, "/r/sub")), ["ok"]);
+        assert!(node(root, "/r/sub").size_is_partial());
+        assert!(root.size_is_partial());
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].path, Path::new("/r/sub/locked"));
+        assert_eq!(report.errors[0].kind, std::io::ErrorKind::PermissionDenied);
+
+        // 根路径本身的错误直接返回
+        let missing = Node::scan_with_fs(
+            PathBuf::from("/missing"),
+            ScanOptions::default(),
+            &fs,
+            &mut |_| {},
+        );
+        assert!(matches!(missing, Err(FswhyError::NotFound(_))));
+    }
+
+    #[test]
+    fn stats_depth_limits_dir_finished_events() {
+        let fs = MemoryFs::new().file("/r/a/b/c", 1);
+        let finished = |stats_depth| {
+            let mut paths = Vec::new();
+            let options = ScanOptions {
+                stats_depth,
+                ..ScanOptions::default()
+            };
+            let mut progress = |event: ScanProgress<'_>| {
+                if let ScanProgress::DirFinished { path, .. } = event {
+                    paths.push(path.to_path_buf());
+                }
+            };
+            Node::scan_with_fs(PathBuf::from("/r"), options, &fs, &mut progress).unwrap();
+            paths.sort();
+            paths
+        };
+        assert!(finished(0).is_empty());
+        assert_eq!(finished(1), [Path::new("/r"), Path::new("/r/a")]);
+        assert_eq!(finished(2).len(), 3);
+    }
+
+    #[test]
+    fn scan_symlinks() {
+        let fs = MemoryFs::new()
+            .file("/r/data/big", 1000)
+            .symlink("/r/link", "data")
+            .symlink("/r/data/loop", "..");
+
+        // 默认不跟随：链接记为符号链接，大小为链接本身（目标路径的长度）
+        let root = scan_memory(&fs, "/r", ScanOptions::default()).root;
+        let link = node(&root, "/r/link");
+        assert!(matches!(link.kind(), Symlink));
+        assert_eq!(link.size(), "data".len() as u64);
+        assert_eq!(root.size(), 1000 + 4 + 2);
+
+        // 跟随时链接指向的目录计入大小，指向祖先目录的链接不会形成循环
+        let options = ScanOptions {
+            follow_symlinks: true,
+            ..ScanOptions::default()
+        };
+        let root = scan_memory(&fs, "/r", options).root;
+        assert!(node(&root, "/r/link").kind().is_dir());
+        assert_eq!(node(&root, "/r/link").size(), 1000 + 2);
+        assert!(matches!(node(&root, "/r/data/loop").kind(), Symlink));
+        assert_eq!(root.size(), 2 * (1000 + 2));
+    }
+
+    #[test]
+    fn recompute_sizes_after_mutation() {
+        let fs = MemoryFs::new()
+            .file("/r/a", 100)
+            .file("/r/sub/b", 50)
+            .file("/r/sub/c", 25)
+            .file("/r/other/d", 10);
+        let mut root = scan_memory(&fs, "/r", ScanOptions::default()).root;
+        assert_eq!(root.size(), 185);
+
+        // 直接修改子节点：删除一个文件、修改一个文件的大小、加入一个新文件
+        let Directory(sub) = &mut root.find_mut(Path::new("/r/sub")).unwrap().kind else {
+            panic!("/r/sub is not a directory");
+        };
+        sub.children
+            .retain(|child| child.path != Path::new("/r/sub/c"));
+        root.find_mut(Path::new("/r/other/d")).unwrap().size = 1000;
+        let Directory(other) = &mut root.find_mut(Path::new("/r/other")).unwrap().kind else {
+            panic!("/r/other is not a directory");
+        };
+        other.children.push(Node {
+            path: PathBuf::from("/r/other/e"),
+            size: 5,
+            kind: File,
+            link: None,
+            mtime: None,
+            owner: None,
+        });
+
+        assert_eq!(root.recompute_sizes(), 100 + 50 + 1000 + 5);
+        assert_eq!(root.size(), 1155);
+        assert_eq!(node(&root, "/r/sub").size(), 50);
+        assert_eq!(node(&root, "/r/sub").descendant_file_count(), 1);
+        assert_eq!(node(&root, "/r/other").size(), 1005);
+        assert_eq!(node(&root, "/r/other").descendant_file_count(), 2);
+        assert_eq!(root.descendant_file_count(), 4);
+        assert_eq!(root.descendant_dir_count(), 2);
+    }
+
+    #[test]
+    fn include_dir_size_matches_du() {
+        let fs = MemoryFs::new()
+            .file("/r/a", 100)
+            .file("/r/sub/b", 50)
+            .mount("/r/mnt")
+            .file("/r/mnt/c", 1000);
+        let options = ScanOptions {
+            include_dir_size: true,
+            same_filesystem: true,
+            ..ScanOptions::default()
+        };
+        let root = scan_memory(&fs, "/r", options).root;
+
+        // 与 `du -x` 一致：目录大小为自身占用的空间加上所有子条目
+        let sub = node(&root, "/r/sub");
+        assert_eq!(sub.size(), MEMORY_DIR_LEN + 50);
+        assert_eq!(root.size(), MEMORY_DIR_LEN + 100 + sub.size());
+        let Directory(prop) = root.kind() else {
+            panic!("root is not a directory");
+        };
+        assert_eq!(prop.own_size(), MEMORY_DIR_LEN);
+
+        // 其他文件系统的挂载点既不进入，自身也不计入
+        let mnt = node(&root, "/r/mnt");
+        assert_eq!(mnt.size(), 0);
+        assert!(matches!(mnt.kind(), Directory(prop) if prop.is_other_filesystem()));
+
+        // 默认只统计内容
+        let root = scan_memory(&fs, "/r", ScanOptions::default()).root;
+        assert_eq!(root.size(), 100 + 50 + 1000);
+    }
 }
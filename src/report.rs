@@ -0,0 +1,505 @@
+//! 基于文件树的统计报告
+//!
+//! 本模块中的方法只读取已扫描的 [`Node`] 树，不依赖终端，也不会修改任何展开状态。
+
+use crate::model::{Node, NodeKind, SizeMode};
+use crate::vfs::{FsProvider, StdFs};
+use std::cmp::Ordering;
+use std::collections::{BTreeMap, BinaryHeap, HashMap};
+use std::fs::File;
+use std::hash::{DefaultHasher, Hasher};
+use std::io::Read;
+use std::path::{Path, PathBuf};
+
+/// [`Node::size_by_extension`] 中没有扩展名的文件所用的键
+pub const NO_EXTENSION: &str = "(none)";
+
+/// [`Node::diff`] 中一个条目的变化
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct PathChange {
+    /// 相对于根目录的路径
+    pub path: PathBuf,
+    /// 旧的大小，新增的条目为 `None`
+    pub old: Option<u64>,
+    /// 新的大小，已移除的条目为 `None`
+    pub new: Option<u64>,
+}
+
+impl PathChange {
+    /// 大小变化的字节数，增长为正
+    pub fn delta(&self) -> i64 {
+        self.new.unwrap_or(0) as i64 - self.old.unwrap_or(0) as i64
+    }
+}
+
+/// [`Node::compression_anomalies`] 计入的最小差值：1 MiB，避免小文件因块对齐被误报
+pub const ANOMALY_MIN_DIFF: u64 = 1024 * 1024;
+
+impl Node {
+    /// 返回子树中最大的 `n` 个节点（文件与目录，不含自身），按大小降序，大小相同时按路径排序
+    pub fn top_n_by_size(&self, n: usize) -> Vec<&Node> {
+        self.top_n_by_size_filtered(n, |_| true)
+    }
+
+    /// 同 [`top_n_by_size`](Self::top_n_by_size)，但只统计满足 `filter` 的节点
+    ///
+    /// 例如 `node.top_n_by_size_filtered(10, |n| !n.kind().is_dir())` 返回最大的 10 个文件。
+    pub fn top_n_by_size_filtered(&self, n: usize, filter: impl Fn(&Node) -> bool) -> Vec<&Node> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        // 堆顶为当前保留项中排名最靠后的节点
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for (node, _) in self.iter().skip(1) {
+            if filter(node) {
+                heap.push(BySizeDesc(node));
+                if heap.len() > n {
+                    heap.pop();
+                }
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|ranked| ranked.0)
+            .collect()
+    }
+
+    /// 各直接子节点的路径、大小与占自身大小的百分比（0 ~ 100），按大小降序，大小相同时按路径排序
+    ///
+    /// 不是目录时返回空列表；自身大小为 0 时百分比均为 0。
+    pub fn immediate_children_summary(&self) -> Vec<(&Path, u64, f64)> {
+        let NodeKind::Directory(prop) = self.kind() else {
+            return Vec::new();
+        };
+        let total = self.size();
+        let mut summary: Vec<(&Path, u64, f64)> = prop
+            .children()
+            .iter()
+            .map(|child| {
+                let percent = if total == 0 {
+                    0.0
+                } else {
+                    child.size() as f64 / total as f64 * 100.0
+                };
+                (child.path(), child.size(), percent)
+            })
+            .collect();
+        summary.sort_by(|a, b| b.1.cmp(&a.1).then_with(|| a.0.cmp(b.0)));
+        summary
+    }
+
+    /// 返回子树中最深的 `n` 个节点（不含自身）及其深度（直接子节点为 1），按深度降序，
+    /// 深度相同时按大小降序、路径升序
+    ///
+    /// 用于找出埋得很深的数据，如层层嵌套的构建产物或意外的递归复制。
+    pub fn deepest_paths(&self, n: usize) -> Vec<(usize, &Node)> {
+        if n == 0 {
+            return Vec::new();
+        }
+
+        // 堆顶为当前保留项中排名最靠后的节点
+        let mut heap = BinaryHeap::with_capacity(n + 1);
+        for (node, depth) in self.iter().skip(1) {
+            heap.push(ByDepthDesc(depth, BySizeDesc(node)));
+            if heap.len() > n {
+                heap.pop();
+            }
+        }
+        heap.into_sorted_vec()
+            .into_iter()
+            .map(|ByDepthDesc(depth, ranked)| (depth, ranked.0))
+            .collect()
+    }
+
+    /// 返回空间最集中的至多 `n` 个目录（不含自身），其中任意两个都不是祖先与后代关系，按大小降序
+    ///
+    /// 按大小降序依次考虑各目录：与已选目录无关的目录在名额未满时加入；
+    /// 已选目录的后代若占其一半以上的大小，则取代该目录，使结果落在空间真正集中的较深目录上；
+    /// 其余后代被跳过，避免同一部分空间被重复报告。
+    pub fn largest_non_overlapping(&self, n: usize) -> Vec<&Node> {
+        if n == 0 {
+            return Vec::new();
+        }
+        let mut dirs: Vec<BySizeDesc> = self
+            .iter()
+            .skip(1)
+            .filter(|(node, _)| node.kind().is_dir())
+            .map(|(node, _)| BySizeDesc(node))
+            .collect();
+        dirs.sort();
+
+        let mut selected: Vec<&Node> = Vec::with_capacity(n);
+        for BySizeDesc(dir) in dirs {
+            match selected
+                .iter()
+                .position(|chosen| dir.path().starts_with(chosen.path()))
+            {
+                Some(index) if dir.size() * 2 > selected[index].size() => selected[index] = dir,
+                None if selected.len() < n => selected.push(dir),
+                _ => {}
+            }
+        }
+        selected.sort_by(|a, b| BySizeDesc(a).cmp(&BySizeDesc(b)));
+        selected
+    }
+
+    /// 返回子树中最大的文件（不含目录与符号链接；自身是文件时返回自身），大小相同时取路径较小者
+    ///
+    /// 超出最大深度的子树中的文件不在树中，不计入。
+    pub fn largest_file(&self) -> Option<&Node> {
+        if matches!(self.kind(), NodeKind::File) {
+            return Some(self);
+        }
+        self.top_n_by_size_filtered(1, |node| matches!(node.kind(), NodeKind::File))
+            .pop()
+    }
+
+    /// 与 `previous`（同一路径较早的扫描结果）相比大小发生变化的条目，值为变化的字节数
+    ///
+    /// 以路径对应两棵树中的节点；新出现的条目按从 0 增长计算，大小不变或已不存在的条目不包含在内。
+    pub fn size_changes(&self, previous: &Node) -> HashMap<PathBuf, i64> {
+        let before: HashMap<&Path, u64> = previous
+            .iter()
+            .map(|(node, _)| (node.path(), node.size()))
+            .collect();
+        self.iter()
+            .filter_map(|(node, _)| {
+                let old = before.get(node.path()).copied().unwrap_or(0);
+                let delta = node.size() as i64 - old as i64;
+                (delta != 0).then(|| (node.path().to_path_buf(), delta))
+            })
+            .collect()
+    }
+
+    /// 与 `old`（较早保存的扫描结果）相比新增、移除与大小变化的条目，按变化量的绝对值降序，相同时按路径排序
+    ///
+    /// 两棵树中的节点以相对于各自根目录的路径对应，因此根路径不同的两次扫描（如移动后的目录）也可以比较；
+    /// 根目录自身不包含在内，总的变化为两个根节点大小之差。新增或移除的目录只报告最上层的一个，不再列出其中的条目。
+    pub fn diff(&self, old: &Node) -> Vec<PathChange> {
+        let before = relative_index(old);
+        let after = relative_index(self);
+        // 父目录同样是新增（或移除）的条目由父目录代表
+        let is_topmost = |path: &Path, other: &HashMap<&Path, &Node>| {
+            path.parent()
+                .is_none_or(|parent| parent.as_os_str().is_empty() || other.contains_key(parent))
+        };
+
+        let mut changes = Vec::new();
+        for (&path, node) in &after {
+            match before.get(path) {
+                Some(prev) if prev.size() != node.size() => changes.push(PathChange {
+                    path: path.to_path_buf(),
+                    old: Some(prev.size()),
+                    new: Some(node.size()),
+                }),
+                Some(_) => {}
+                None if is_topmost(path, &before) => changes.push(PathChange {
+                    path: path.to_path_buf(),
+                    old: None,
+                    new: Some(node.size()),
+                }),
+                None => {}
+            }
+        }
+        for (&path, node) in &before {
+            if !after.contains_key(path) && is_topmost(path, &after) {
+                changes.push(PathChange {
+                    path: path.to_path_buf(),
+                    old: Some(node.size()),
+                    new: None,
+                });
+            }
+        }
+        changes.sort_by(|a, b| {
+            b.delta()
+                .unsigned_abs()
+                .cmp(&a.delta().unsigned_abs())
+                .then_with(|| a.path.cmp(&b.path))
+        });
+        changes
+    }
+
+    /// 子树中目录的最大嵌套深度（自身为 0，文件与符号链接不计）
+    ///
+    /// 只统计树中的节点：超出最大深度、未保留子节点的子树按其自身深度计算。
+    /// 扫描时的完整深度见 [`ScanStats::max_depth`](crate::model::ScanStats::max_depth)。
+    pub fn max_depth(&self) -> usize {
+        self.iter()
+            .filter(|(node, _)| node.kind().is_dir())
+            .map(|(_, depth)| depth)
+            .max()
+            .unwrap_or(0)
+    }
+
+    /// 删除该节点后实际能释放的空间
+    ///
+    /// 记录了硬链接信息（扫描时开启去重）的文件，只有当指向同一 inode 的所有链接都在子树内时才计入，
+    /// 且每个 inode 只计一次；否则删除后数据仍被其他链接引用。没有硬链接信息时等于各文件大小之和，
+    /// 即 [`size`](Node::size)。未保留子节点的目录按其总大小计入。
+    pub fn reclaimable_size(&self) -> u64 {
+        let mut total = 0;
+        // inode → (子树内的链接数, 链接总数, 大小)
+        let mut links: HashMap<(u64, u64), (u64, u64, u64)> = HashMap::new();
+        for (node, _) in self.iter() {
+            match (node.kind(), node.hard_link()) {
+                (NodeKind::Directory(prop), _) => {
+                    if prop.is_truncated() {
+                        total += node.size();
+                    }
+                }
+                (_, Some(link)) => {
+                    let entry = links.entry(link.inode()).or_insert((0, link.nlink(), 0));
+                    entry.0 += 1;
+                    // 重复出现的链接大小记为 0，取组内最大值即 inode 的大小
+                    entry.2 = entry.2.max(node.size());
+                }
+                (_, None) => total += node.size(),
+            }
+        }
+        total
+            + links
+                .values()
+                .filter(|(count, nlink, _)| count >= nlink)
+                .map(|(_, _, size)| size)
+                .sum::<u64>()
+    }
+
+    /// 按扩展名（小写，不含 `.`）汇总文件的总大小与数量，没有扩展名的文件归入 [`NO_EXTENSION`]
+    ///
+    /// 只统计保留在树中的文件，超出最大深度的子树不计入。
+    pub fn size_by_extension(&self) -> BTreeMap<String, (u64, u64)> {
+        let mut map = BTreeMap::new();
+        for (node, _) in self.iter().skip(1) {
+            if matches!(node.kind(), NodeKind::File) {
+                let ext = node.path().extension().map_or_else(
+                    || NO_EXTENSION.to_string(),
+                    |ext| ext.to_string_lossy().to_lowercase(),
+                );
+                let entry: &mut (u64, u64) = map.entry(ext).or_default();
+                entry.0 += node.size();
+                entry.1 += 1;
+            }
+        }
+        map
+    }
+
+    /// 按所有者的用户 ID 汇总文件的总大小与数量
+    ///
+    /// 只统计保留在树中的文件，超出最大深度的子树与没有所有者信息（非 Unix 平台）的文件不计入。
+    pub fn size_by_owner(&self) -> BTreeMap<u32, (u64, u64)> {
+        let mut map = BTreeMap::new();
+        for (node, _) in self.iter().skip(1) {
+            if matches!(node.kind(), NodeKind::File)
+                && let Some(owner) = node.owner()
+            {
+                let entry: &mut (u64, u64) = map.entry(owner.uid()).or_default();
+                entry.0 += node.size();
+                entry.1 += 1;
+            }
+        }
+        map
+    }
+
+    /// 查找表观大小与实际占用空间差异显著的文件，返回 `(路径, 表观大小, 占用空间)`
+    ///
+    /// 用于发现稀疏文件或透明压缩（btrfs/zfs）的数据：两者相差至少 [`ANOMALY_MIN_DIFF`]，
+    /// 且相差超过较大者的一半时才计入。会重新读取每个文件的元数据；按差值降序排列。
+    pub fn compression_anomalies(&self) -> Vec<(&Path, u64, u64)> {
+        let mut result = Vec::new();
+        for (node, _) in self.iter().skip(1) {
+            if !matches!(node.kind(), NodeKind::File) {
+                continue;
+            }
+            let Ok(meta) = StdFs.symlink_metadata(node.path()) else {
+                continue;
+            };
+            let apparent = SizeMode::Apparent.size_of(&meta);
+            let allocated = SizeMode::Allocated.size_of(&meta);
+            let diff = apparent.abs_diff(allocated);
+            if diff >= ANOMALY_MIN_DIFF && diff > apparent.max(allocated) / 2 {
+                result.push((node.path(), apparent, allocated));
+            }
+        }
+        result.sort_by(|a, b| {
+            b.1.abs_diff(b.2)
+                .cmp(&a.1.abs_diff(a.2))
+                .then_with(|| a.0.cmp(b.0))
+        });
+        result
+    }
+
+    /// 查找内容相同的文件，返回重复文件组
+    ///
+    /// 只有与其他文件大小相同的非空文件才会读取内容并计算哈希，大小与哈希均相同视为重复；
+    /// 无法读取的文件被跳过。组内按路径排序，各组按可回收空间降序排列。
+    pub fn find_duplicates(&self) -> Vec<Vec<&Node>> {
+        let mut by_size: HashMap<u64, Vec<&Node>> = HashMap::new();
+        for (node, _) in self.iter().skip(1) {
+            if matches!(node.kind(), NodeKind::File) && node.size() > 0 {
+                by_size.entry(node.size()).or_default().push(node);
+            }
+        }
+
+        let mut groups: Vec<Vec<&Node>> = Vec::new();
+        for candidates in by_size.into_values().filter(|c| c.len() > 1) {
+            let mut by_hash: HashMap<u64, Vec<&Node>> = HashMap::new();
+            for node in candidates {
+                if let Ok(hash) = hash_file(node.path()) {
+                    by_hash.entry(hash).or_default().push(node);
+                }
+            }
+            groups.extend(by_hash.into_values().filter(|g| g.len() > 1));
+        }
+        for group in &mut groups {
+            group.sort_by(|a, b| a.path().cmp(b.path()));
+        }
+        groups.sort_by(|a, b| {
+            reclaimable(b)
+                .cmp(&reclaimable(a))
+                .then_with(|| a[0].path().cmp(b[0].path()))
+        });
+        groups
+    }
+
+    /// 返回子树中所有不含任何文件或符号链接的目录（含自身），只包含空目录的目录同样视为空
+    ///
+    /// 结果按后序排列，子目录总在父目录之前，可按顺序逐个删除；合成根目录不计入结果。
+    pub fn empty_directories(&self) -> Vec<&Path> {
+        let mut result = Vec::new();
+        collect_empty_dirs(self, &mut result);
+        result
+    }
+
+    /// 返回子树中所有大小为 0 的文件（见 [`Node::is_zero_byte_file`]），按树中顺序排列
+    ///
+    /// 锁文件、占位文件或写入失败留下的空文件在按大小排序的视图中几乎不可见，可借此集中查看。
+    pub fn zero_byte_files(&self) -> Vec<&Path> {
+        self.iter()
+            .filter(|(node, _)| node.is_zero_byte_file())
+            .map(|(node, _)| node.path())
+            .collect()
+    }
+}
+
+/// 重复文件组中除保留一份外可回收的空间
+pub fn reclaimable(group: &[&Node]) -> u64 {
+    group.first().map_or(0, |node| {
+        node.size() * (group.len() as u64).saturating_sub(1)
+    })
+}
+
+/// 计算文件内容的哈希
+fn hash_file(path: &Path) -> std::io::Result<u64> {
+    let mut file = File::open(path)?;
+    let mut hasher = DefaultHasher::new();
+    let mut buf = vec![0; 64 * 1024];
+    loop {
+        let n = file.read(&mut buf)?;
+        if n == 0 {
+            break;
+        }
+        hasher.write(&buf[..n]);
+    }
+    Ok(hasher.finish())
+}
+
+/// 子树中除根节点外的所有节点，以相对于根目录的路径为键
+fn relative_index(root: &Node) -> HashMap<&Path, &Node> {
+    root.iter()
+        .skip(1)
+        .map(|(node, _)| (node.relative_path(root.path()), node))
+        .collect()
+}
+
+/// 后序收集空目录，返回节点自身是否为空目录
+fn collect_empty_dirs<'a>(node: &'a Node, result: &mut Vec<&'a Path>) -> bool {
+    let NodeKind::Directory(prop) = node.kind() else {
+        return false;
+    };
+    // 未保留子节点的目录只能依据统计判断：目录自身不计大小，符号链接有大小；
+    // 其他文件系统的挂载点内容未知，不视为空
+    let is_empty = if prop.is_other_filesystem() {
+        false
+    } else if prop.is_truncated() {
+        prop.file_count() == 0 && node.size() == 0
+    } else {
+        let mut all_empty = true;
+        for child in prop.children() {
+            all_empty &= collect_empty_dirs(child, result);
+        }
+        all_empty
+    };
+    if is_empty && !node.is_multi_root() {
+        result.push(node.path());
+    }
+    is_empty
+}
+
+/// 按大小降序、路径升序排列的包装，越"小"排名越靠前
+struct BySizeDesc<'a>(&'a Node);
+
+impl Ord for BySizeDesc<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other
+            .0
+            .size()
+            .cmp(&self.0.size())
+            .then_with(|| self.0.path().cmp(other.0.path()))
+    }
+}
+
+impl PartialOrd for BySizeDesc<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl PartialEq for BySizeDesc<'_> {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for BySizeDesc<'_> {}
+
+/// 按深度降序排列的包装，深度相同时同 [`BySizeDesc`]
+#[derive(PartialEq, Eq)]
+struct ByDepthDesc<'a>(usize, BySizeDesc<'a>);
+
+impl Ord for ByDepthDesc<'_> {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.0.cmp(&self.0).then_with(|| self.1.cmp(&other.1))
+    }
+}
+
+impl PartialOrd for ByDepthDesc<'_> {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+/// 用户 ID 到用户名的映射，读取自 `/etc/passwd`；无法读取时为空
+///
+/// 不查询 NSS，LDAP 等目录服务中的用户不会出现在结果中。
+#[cfg(unix)]
+pub fn user_names() -> HashMap<u32, String> {
+    let Ok(passwd) = std::fs::read_to_string("/etc/passwd") else {
+        return HashMap::new();
+    };
+    passwd
+        .lines()
+        .filter_map(|line| {
+            let mut fields = line.split(':');
+            let name = fields.next()?;
+            let uid = fields.nth(1)?.parse().ok()?;
+            Some((uid, name.to_string()))
+        })
+        .collect()
+}
+
+/// 用户 ID 到用户名的映射，非 Unix 平台上总为空
+#[cfg(not(unix))]
+pub fn user_names() -> HashMap<u32, String> {
+    HashMap::new()
+}
@@ -31,6 +31,16 @@ pub(crate) struct Theme {
     pub(crate) file_gradient_start: Color,
     #[serde(default)]
     pub(crate) file_gradient_end: Color,
+    #[serde(default = "default_large_file")]
+    pub(crate) large_file: Color,
+    #[serde(default = "default_size_gradient_start")]
+    pub(crate) size_gradient_start: Color,
+    #[serde(default = "default_size_gradient_end")]
+    pub(crate) size_gradient_end: Color,
+    #[serde(default = "default_grown")]
+    pub(crate) grown: Color,
+    #[serde(default = "default_shrunk")]
+    pub(crate) shrunk: Color,
 }
 
 /// 预设颜色宏
@@ -136,7 +146,7 @@ define_preset_colors! {
 #[serde(untagged)]
 pub(crate) enum Color {
     Preset { name: String },
-    RGB { r: u8, g: u8, b: u8 },
+    Rgb { r: u8, g: u8, b: u8 },
 }
 
 impl Default for Theme {
@@ -163,30 +173,70 @@ impl Default for Theme {
             highlight_end: Color::Preset {
                 name: "reset".to_string(),
             },
-            dir_gradient_start: Color::RGB {
+            dir_gradient_start: Color::Rgb {
                 r: 58,
                 g: 123,
                 b: 213,
             },
-            dir_gradient_end: Color::RGB {
+            dir_gradient_end: Color::Rgb {
                 r: 0,
                 g: 210,
                 b: 255,
             },
-            file_gradient_start: Color::RGB {
+            file_gradient_start: Color::Rgb {
                 r: 180,
                 g: 180,
                 b: 180,
             },
-            file_gradient_end: Color::RGB {
+            file_gradient_end: Color::Rgb {
                 r: 255,
                 g: 200,
                 b: 120,
             },
+            large_file: default_large_file(),
+            size_gradient_start: default_size_gradient_start(),
+            size_gradient_end: default_size_gradient_end(),
+            grown: default_grown(),
+            shrunk: default_shrunk(),
         }
     }
 }
 
+// 以下字段在旧主题文件中可能缺失，缺失时使用与默认主题一致的值
+fn default_large_file() -> Color {
+    Color::Preset {
+        name: "red".to_string(),
+    }
+}
+
+fn default_size_gradient_start() -> Color {
+    Color::Rgb {
+        r: 120,
+        g: 120,
+        b: 120,
+    }
+}
+
+fn default_size_gradient_end() -> Color {
+    Color::Rgb {
+        r: 255,
+        g: 110,
+        b: 110,
+    }
+}
+
+fn default_grown() -> Color {
+    Color::Preset {
+        name: "green".to_string(),
+    }
+}
+
+fn default_shrunk() -> Color {
+    Color::Preset {
+        name: "red".to_string(),
+    }
+}
+
 impl Default for Color {
     fn default() -> Self {
         Color::Preset {
@@ -203,7 +253,7 @@ impl Color {
                 let preset = PresetColor::parse(name)?;
                 Ok(preset.to_ansi().to_string())
             }
-            Color::RGB { r, g, b } => Ok(format!("\x1b[38;2;{};{};{}m", r, g, b)),
+            Color::Rgb { r, g, b } => Ok(format!("\x1b[38;2;{};{};{}m", r, g, b)),
         }
     }
 
@@ -214,7 +264,7 @@ impl Color {
                 let preset = PresetColor::parse(name)?;
                 Ok(preset.to_rgb())
             }
-            Color::RGB { r, g, b } => Ok((*r, *g, *b)),
+            Color::Rgb { r, g, b } => Ok((*r, *g, *b)),
         }
     }
 
@@ -249,6 +299,11 @@ impl Theme {
         self.dir_gradient_end.validate()?;
         self.file_gradient_start.validate()?;
         self.file_gradient_end.validate()?;
+        self.large_file.validate()?;
+        self.size_gradient_start.validate()?;
+        self.size_gradient_end.validate()?;
+        self.grown.validate()?;
+        self.shrunk.validate()?;
         Ok(())
     }
 }
@@ -256,10 +311,10 @@ impl Theme {
 /// 从环境变量或默认路径加载主题
 pub(crate) fn load_theme_from_env_or_default() -> Theme {
     // 优先从环境变量加载
-    if let Ok(path) = env::var("FSWHY_THEME") {
-        if let Ok(theme) = Theme::load_from_file(Path::new(&path)) {
-            return theme;
-        }
+    if let Ok(path) = env::var("FSWHY_THEME")
+        && let Ok(theme) = Theme::load_from_file(Path::new(&path))
+    {
+        return theme;
     }
     // 其次从当前目录加载
     if let Ok(theme) = Theme::load_from_file(Path::new("theme.toml")) {
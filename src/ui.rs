@@ -2,168 +2,654 @@
 //!
 //! 本模块将内部的 [`UiState`] 转换为人类可读的终端界面，并将原始用户按键转换为可操作的 [`Action`]。
 
+use crate::format::{display_name, format_delta, format_size, format_time, truncate_middle};
+use crate::keys::KeyMap;
+use crate::model::Node;
 use crate::model::NodeKind::*;
 use crate::theme::Color;
-use crate::ui_state::{Action, SortMode, UiState, ViewItem};
+use crate::ui_state::{Action, DisplayColumn, InputMode, UiState, ViewItem, sort_label};
 
-use crossterm::event::{self, Event, KeyCode, KeyEventKind, KeyModifiers};
-use crossterm::terminal;
+use crossterm::event::{
+    self, DisableMouseCapture, EnableMouseCapture, Event, KeyCode, KeyEvent, KeyEventKind,
+    KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
+use crossterm::execute;
+use crossterm::terminal::{self, EnterAlternateScreen, LeaveAlternateScreen};
+use std::borrow::Cow;
 use std::io::{self, Write};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
-/// raw mode守卫，析构时恢复
-#[allow(dead_code)]
+/// 比例条宽度
+const BAR_WIDTH: usize = 8;
+/// 加粗
+const BOLD: &str = "\x1b[1m";
+/// 取消加粗
+const NORMAL_INTENSITY: &str = "\x1b[22m";
+
+/// 截断名称时至少保留的字符数
+const MIN_NAME_CHARS: usize = 8;
+
+/// 树以外固定占用的行数：两行标题、滚动提示、状态栏（不含帮助栏与分布条）
+const CHROME_LINES: usize = 4;
+
+/// 树的第一行在终端中的行号（从 0 开始）：位于两行标题与滚动提示之后
+const TREE_TOP_ROW: usize = 3;
+
+/// 分布条中带标签的段至少占用的列数，更小的子节点归入 "other"
+const MIN_SEGMENT_WIDTH: usize = 8;
+
+/// 终端守卫：进入 raw mode 与备用屏幕，析构时恢复
 pub struct RawModeGuard;
 
+impl RawModeGuard {
+    /// 进入 raw mode 与备用屏幕，并开启鼠标捕获
+    pub fn enter() -> io::Result<Self> {
+        terminal::enable_raw_mode()?;
+        if let Err(e) = execute!(io::stdout(), EnterAlternateScreen, EnableMouseCapture) {
+            let _ = terminal::disable_raw_mode();
+            return Err(e);
+        }
+        Ok(Self)
+    }
+}
+
 impl Drop for RawModeGuard {
     fn drop(&mut self) {
+        let _ = execute!(io::stdout(), DisableMouseCapture, LeaveAlternateScreen);
         let _ = terminal::disable_raw_mode();
     }
 }
 
+/// 根据终端大小计算树可用的行数，无法获取终端大小时返回 `None`
+pub fn viewport_height(state: &UiState) -> Option<usize> {
+    let (cols, rows) = terminal::size().ok()?;
+    let cols = usize::from(cols).max(1);
+    let help_lines = help_line(state).chars().count().div_ceil(cols).max(1);
+    let breakdown_lines = usize::from(state.show_breakdown);
+    Some(
+        usize::from(rows)
+            .saturating_sub(CHROME_LINES + help_lines + breakdown_lines)
+            .max(1),
+    )
+}
+
+/// 终端宽度（列数），无法获取终端大小时返回 `None`
+pub fn terminal_width() -> Option<usize> {
+    terminal::size()
+        .ok()
+        .map(|(cols, _)| usize::from(cols).max(1))
+}
+
 /// 渲染文件树
+///
+/// 整帧先写入缓冲区再一次性输出，raw mode 下换行需要 `\r\n`。
+/// 只展平视口内的项，渐变色范围也按当前屏幕计算。
 pub fn render(state: &UiState) {
-    let view = state.flatten_view();
-    let total = view.len();
-    let max_idx_width = total.saturating_sub(1).to_string().len().max(1);
+    if state.input_mode == InputMode::Jump {
+        return render_jump(state);
+    }
+    let total = state.visible_len();
+    let max_idx_width = state.max_dir_id().to_string().len();
     let height = state.viewport_height.max(1);
 
-    // 计算视口范围
+    // 视口范围由滚动偏移决定
     let cursor = state.cursor.min(total.saturating_sub(1));
-    let start = if total <= height {
-        0
-    } else if cursor + 1 <= height {
-        0
-    } else {
-        (cursor + 1 - height).min(total.saturating_sub(height))
-    };
+    let start = state.scroll_offset.min(total.saturating_sub(1));
     let end = (start + height).min(total);
     let remaining_above = start;
     let remaining_below = total.saturating_sub(end);
 
-    // 计算大小范围（用于渐变色）
-    let (dir_min, dir_max) = size_range(&view, true).unwrap_or((0, 0));
-    let (file_min, file_max) = size_range(&view, false).unwrap_or((0, 0));
-
-    // 清屏
-    print!("\x1b[2J\x1b[H");
+    let view = state.flatten_window(start, end - start);
+    let ctx = RowContext::new(&view);
+    let mut lines = Vec::with_capacity(height + CHROME_LINES + 1);
 
-    // 标题
-    println!(
+    lines.push(title_line(state));
+    lines.push(format!(
         "--- File Tree (Total: {}, Showing: {}-{}) ---",
         total,
         start,
         end.saturating_sub(1)
-    );
+    ));
     if remaining_above > 0 || remaining_below > 0 {
-        println!(
+        lines.push(format!(
             "(More: above {}, below {})",
             remaining_above, remaining_below
-        );
+        ));
+    } else {
+        lines.push(String::new());
     }
 
     // 渲染每一行
-    for (index, item) in view.iter().enumerate().skip(start).take(end - start) {
+    for (index, item) in (start..).zip(&view) {
         let prefix = "  ".repeat(item.depth);
-        let idx_str = format!("{:width$}", index, width = max_idx_width);
-        let icon = match item.node.kind() {
-            Directory(_) => {
-                if state.expanded_nodes.contains(&item.node) {
-                    "[-]"
-                } else {
-                    "[+]"
-                }
-            }
-            File => "   ",
+        // 只有目录显示编号，编号在展开/折叠后保持不变
+        let idx_str = match state.dir_id(item.node) {
+            Some(id) => format!("{:width$}", id, width = max_idx_width),
+            None => " ".repeat(max_idx_width),
         };
-
-        let size = item.node.size();
-        let size_str = format_size(size);
-
         let is_selected = index == cursor;
         let (hl_start, hl_end) = if is_selected {
             (
-                state.theme.highlight_start.to_ansi().unwrap_or_default(),
-                state.theme.highlight_end.to_ansi().unwrap_or_default(),
+                ansi(state, &state.theme.highlight_start),
+                ansi(state, &state.theme.highlight_end),
             )
         } else {
             (String::new(), String::new())
         };
         let selection = if is_selected { ">" } else { " " };
+        let mark = if state.is_marked(item.node) {
+            "✓"
+        } else {
+            " "
+        };
 
-        // 渐变色
-        let name_color = match item.node.kind() {
-            Directory(_) => gradient_color(
-                size,
-                dir_min,
-                dir_max,
-                &state.theme.dir_gradient_start,
-                &state.theme.dir_gradient_end,
-                &state.theme.dir,
-            ),
-            File => gradient_color(
-                size,
-                file_min,
-                file_max,
-                &state.theme.file_gradient_start,
-                &state.theme.file_gradient_end,
-                &state.theme.file,
-            ),
+        let line = |name_limit| {
+            format!(
+                "{}{}{} {} {} {}{}{}",
+                hl_start,
+                selection,
+                mark,
+                idx_str,
+                format_bar(item),
+                prefix,
+                format_row(state, &ctx, item, name_limit),
+                hl_end
+            )
         };
-        let fg_reset = state.theme.fg_reset.to_ansi().unwrap_or_default();
+        let mut row = line(None);
+        // 超出终端宽度时截去名称中间部分，避免换行打乱布局
+        let overflow = visible_width(&row).saturating_sub(state.viewport_width.max(1));
+        if overflow > 0 {
+            let name_len = display_name(item.node.path()).chars().count();
+            row = line(Some(name_len.saturating_sub(overflow).max(MIN_NAME_CHARS)));
+        }
+        lines.push(row);
+    }
 
-        println!(
-            "{}{} {}{} {} {}{}{} ({}){}",
-            hl_start,
-            selection,
-            idx_str,
-            prefix,
-            icon,
-            name_color,
-            item.node
-                .path()
-                .file_name()
-                .unwrap_or_default()
-                .to_string_lossy(),
-            fg_reset,
-            size_str,
-            hl_end
-        );
+    if state.show_breakdown {
+        lines.push(match state.breakdown_dir() {
+            Some(dir) => format_breakdown(state, dir),
+            None => String::new(),
+        });
     }
 
     // 状态栏
     if let Some(status) = &state.status {
         let color = if status.is_error {
-            state.theme.error.to_ansi().unwrap_or_default()
+            ansi(state, &state.theme.error)
         } else {
             String::new()
         };
-        let reset = state.theme.reset.to_ansi().unwrap_or_default();
-        println!("{}{}{}", color, status.text, reset);
+        let reset = ansi(state, &state.theme.reset);
+        lines.push(format!("{}{}{}", color, status.text, reset));
+    } else if let Some(item) = cursor.checked_sub(start).and_then(|i| view.get(i)) {
+        // 没有消息时显示光标处条目的完整路径
+        let path = std::path::absolute(item.node.path())
+            .unwrap_or_else(|_| item.node.path().to_path_buf());
+        let path = path.to_string_lossy();
+        lines.push(truncate_middle(&path, state.viewport_width.max(1)).into_owned());
     } else {
-        println!();
+        lines.push(String::new());
     }
 
     // 帮助栏
-    let sort_label = match state.sort_mode {
-        SortMode::NameAsc => "name",
-        SortMode::SizeDesc => "size",
+    lines.push(help_line(state));
+    write_frame(&lines);
+}
+
+/// 渲染模糊跳转：在树的位置列出得分最高的匹配路径，选中项高亮
+fn render_jump(state: &UiState) {
+    let height = state.viewport_height.max(1);
+    let matches = state.jump_matches(height);
+    let selected = state.jump_selected.min(matches.len().saturating_sub(1));
+    let mut lines = Vec::with_capacity(height + CHROME_LINES + 1);
+    lines.push(title_line(state));
+    lines.push(format!("--- Jump to path (Matches: {}) ---", matches.len()));
+    lines.push(String::new());
+
+    for (index, node) in matches.iter().enumerate() {
+        let (hl_start, hl_end, selection) = if index == selected {
+            (
+                ansi(state, &state.theme.highlight_start),
+                ansi(state, &state.theme.highlight_end),
+                ">",
+            )
+        } else {
+            (String::new(), String::new(), " ")
+        };
+        let size = format!("{:>10}", format_size(node.size(), state.size_format));
+        let suffix = if node.kind().is_dir() { "/" } else { "" };
+        let path = node.relative_path(state.root.path()).to_string_lossy();
+        let budget = state
+            .viewport_width
+            .max(1)
+            .saturating_sub(size.chars().count() + suffix.len() + 3)
+            .max(MIN_NAME_CHARS);
+        lines.push(format!(
+            "{}{} {}  {}{}{}",
+            hl_start,
+            selection,
+            size,
+            truncate_middle(&path, budget),
+            suffix,
+            hl_end
+        ));
+    }
+
+    // 状态栏留空，帮助栏显示查询
+    lines.push(String::new());
+    lines.push(help_line(state));
+    write_frame(&lines);
+}
+
+/// 标题：根目录绝对路径与总大小，以及扫描状态
+fn title_line(state: &UiState) -> String {
+    let scan_status = match state.scanning {
+        Some(items) => Cow::Owned(format!(" {} scanning… {} items", spinner_frame(), items)),
+        None if state.interrupted => Cow::Borrowed(" (scan interrupted)"),
+        None => Cow::Borrowed(""),
+    };
+    format!(
+        "{} ({}){}",
+        state.root_path.display(),
+        format_size(state.root.size(), state.size_format),
+        scan_status
+    )
+}
+
+/// 清屏后输出整帧；raw mode 下换行需要 `\r\n`
+fn write_frame(lines: &[String]) {
+    let mut stdout = io::stdout().lock();
+    let _ = write!(stdout, "\x1b[2J\x1b[H{}", lines.join("\r\n"));
+    let _ = stdout.flush();
+}
+
+/// 后台扫描指示的当前帧，每 100 毫秒切换一次
+fn spinner_frame() -> char {
+    const FRAMES: [char; 10] = ['⠋', '⠙', '⠹', '⠸', '⠼', '⠴', '⠦', '⠧', '⠇', '⠏'];
+    let ticks = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map_or(0, |elapsed| elapsed.as_millis() / 100);
+    FRAMES[(ticks % FRAMES.len() as u128) as usize]
+}
+
+/// 帮助栏文本
+fn help_line(state: &UiState) -> String {
+    if state.input_mode == InputMode::Confirm {
+        return "[y] Yes | [any other key] No > ".to_string();
+    }
+    if state.input_mode == InputMode::Search {
+        return format!(
+            "[Enter] Confirm | [Esc] Clear | Search: {}",
+            state.search_query
+        );
+    }
+    if state.input_mode == InputMode::Move {
+        return format!(
+            "[Enter] Move | [Esc] Cancel | Move to directory: {}",
+            state.move_target
+        );
+    }
+    if state.input_mode == InputMode::Jump {
+        return format!(
+            "[Enter] Jump | [Up/Down] Select | [Esc] Cancel | Jump to: {}",
+            state.jump_query
+        );
+    }
+    let key = |action| state.keys.label(action);
+    let sort_label = sort_label(state.sort_mode);
+    let mut filter_label = if state.search_query.is_empty() {
+        String::new()
+    } else {
+        format!(
+            "Filter: {} [{}] Clear | ",
+            state.search_query,
+            key(Action::ClearSearch)
+        )
+    };
+    if state.min_size > 0 {
+        let status = if state.hide_small { "on" } else { "off" };
+        filter_label = format!(
+            "[{}] Min {} ({}) | {}",
+            key(Action::ToggleMinSize),
+            format_size(state.min_size, state.size_format),
+            status,
+            filter_label
+        );
+    }
+    if state.focus_snapshot.is_some() {
+        filter_label = format!(
+            "Focused [{}] Restore | {}",
+            key(Action::ToggleFocus),
+            filter_label
+        );
+    }
+    if !state.marked.is_empty() {
+        let (paths, total) = state.marked_roots();
+        filter_label = format!(
+            "Marked: {} ({}) | {}",
+            paths.len(),
+            format_size(total, state.size_format),
+            filter_label
+        );
+    }
+    format!(
+        "[{}/{}] Move | [{}/{}] Page | [{}/{}] Fold | [{}] Parent | [{}/{}] Toggle | [{}/{}] All | [{}] Focus | [{}/{}] Sort({})/dir | [{}] Counts | [{}/{}] Column({}) | [{}] Hidden({}) | [{}] Files({}) | [{}] Breakdown | [{}] Search | [{}] Jump | [{}] Rescan | [{}] Open | [{}] Copy path | [{}] Mark | [{}] Move | [{}] Trash | [{}/{}] Delete/marked | [{}] Quit | {}Dir: {} > ",
+        key(Action::MoveDown),
+        key(Action::MoveUp),
+        key(Action::PageUp),
+        key(Action::PageDown),
+        key(Action::Collapse),
+        key(Action::Expand),
+        key(Action::JumpToParent),
+        key(Action::Enter),
+        key(Action::ToggleAtCursor),
+        key(Action::ExpandAll),
+        key(Action::CollapseAll),
+        key(Action::ToggleFocus),
+        key(Action::ToggleSort),
+        key(Action::SortDirectory),
+        sort_label,
+        key(Action::ToggleFileCount),
+        key(Action::CycleColumn),
+        key(Action::ToggleRelative),
+        state.column.label(),
+        key(Action::ToggleHidden),
+        if state.show_hidden { "on" } else { "off" },
+        key(Action::ToggleDirsOnly),
+        if state.dirs_only { "off" } else { "on" },
+        key(Action::ToggleBreakdown),
+        key(Action::StartSearch),
+        key(Action::StartJump),
+        key(Action::Rescan),
+        key(Action::Open),
+        key(Action::Yank),
+        key(Action::ToggleMark),
+        key(Action::StartMove),
+        key(Action::Trash),
+        key(Action::Delete),
+        key(Action::DeleteMarked),
+        key(Action::Quit),
+        filter_label,
+        state.input_buffer
+    )
+}
+
+/// 将当前可见列表逐行写入 `out`（不清屏、不显示光标与帮助栏），用于非交互模式
+pub fn write_tree(state: &UiState, out: &mut impl Write) -> io::Result<()> {
+    let view = state.flatten_view();
+    let ctx = RowContext::new(&view);
+    for item in &view {
+        writeln!(
+            out,
+            "{} {}{}",
+            format_bar(item),
+            "  ".repeat(item.depth),
+            format_row(state, &ctx, item, None)
+        )?;
+    }
+    Ok(())
+}
+
+/// 可见列表的大小范围（用于渐变色）
+struct RowContext {
+    dir_range: (u64, u64),
+    file_range: (u64, u64),
+    size_max: u64,
+}
+
+impl RowContext {
+    fn new(view: &[ViewItem<'_>]) -> Self {
+        Self {
+            dir_range: size_range(view, true).unwrap_or((0, 0)),
+            file_range: size_range(view, false).unwrap_or((0, 0)),
+            size_max: view.iter().map(|item| item.node.size()).max().unwrap_or(0),
+        }
+    }
+}
+
+/// 占父目录比例的条形图与百分比，如 `[###-----]  37.5%`
+fn format_bar(item: &ViewItem<'_>) -> String {
+    let fraction = item.fraction_of_parent().clamp(0.0, 1.0);
+    let filled = (fraction * BAR_WIDTH as f64).round() as usize;
+    format!(
+        "[{}{}] {:5.1}%",
+        "#".repeat(filled),
+        "-".repeat(BAR_WIDTH - filled),
+        fraction * 100.0
+    )
+}
+
+/// 目录空间在直接子节点间的分布：按大小比例分配宽度的各段，标注名称与百分比
+///
+/// 宽度不足 [`MIN_SEGMENT_WIDTH`] 的子节点合并为一段 "other"；开启颜色时相邻的段交替反色显示。
+fn format_breakdown(state: &UiState, dir: &Node) -> String {
+    let Directory(prop) = dir.kind() else {
+        return String::new();
+    };
+    let name = display_name(dir.path());
+    if prop.is_truncated() || prop.is_other_filesystem() {
+        return format!("{name}: contents not scanned");
+    }
+    let total = dir.size();
+    if total == 0 {
+        return format!("{name}: empty");
+    }
+
+    // 每段以 `|` 开头，末尾再补一个 `|`
+    let width = state.viewport_width.saturating_sub(1).max(1);
+    let mut children: Vec<&Node> = prop.children().iter().collect();
+    children.sort_by_key(|child| std::cmp::Reverse(child.size()));
+    let mut segments: Vec<(Cow<str>, u64)> = Vec::new();
+    let mut other = 0;
+    for child in children {
+        if (child.size() as u128 * width as u128 / total as u128) as usize >= MIN_SEGMENT_WIDTH {
+            segments.push((display_name(child.path()), child.size()));
+        } else {
+            other += child.size();
+        }
+    }
+    if other > 0 {
+        segments.push((Cow::Borrowed("other"), other));
+    }
+
+    // 按累计大小取整分配宽度，各段宽度之和恰为总宽度
+    let mut line = String::new();
+    let mut cumulative = 0;
+    let mut start = 0;
+    for (index, (label, size)) in segments.iter().enumerate() {
+        cumulative += size;
+        let end = (cumulative as u128 * width as u128 / total as u128) as usize;
+        let segment_width = end - start;
+        start = end;
+        if segment_width == 0 {
+            continue;
+        }
+        // 空间不足时优先保留百分比，截去名称中间部分
+        let inner = segment_width - 1;
+        let percent = format!("{:.0}%", *size as f64 / total as f64 * 100.0);
+        let text = match inner.checked_sub(percent.len() + 1) {
+            Some(name_width) if name_width > 0 => {
+                format!("{} {percent}", truncate_middle(label, name_width))
+            }
+            _ => truncate_middle(&percent, inner).into_owned(),
+        };
+        let padded = format!("|{text:<inner$}");
+        if state.color && index % 2 == 1 {
+            line.push_str(&format!(
+                "{}{}{}",
+                ansi(state, &state.theme.highlight_start),
+                padded,
+                ansi(state, &state.theme.highlight_end)
+            ));
+        } else {
+            line.push_str(&padded);
+        }
+    }
+    line.push('|');
+    line
+}
+
+/// 格式化单行的图标、名称与大小
+///
+/// `name_limit` 为名称最多显示的字符数，超出时截去中间部分；`None` 表示不截断。
+fn format_row(
+    state: &UiState,
+    ctx: &RowContext,
+    item: &ViewItem<'_>,
+    name_limit: Option<usize>,
+) -> String {
+    let icon = match item.node.kind() {
+        Directory(prop) if prop.is_other_filesystem() => "[=]",
+        Directory(prop) if prop.is_truncated() => "[~]",
+        Directory(_) => {
+            if state.is_expanded(item.node) {
+                "[-]"
+            } else {
+                "[+]"
+            }
+        }
+        File if item.node.is_zero_byte_file() => " ∅ ",
+        File | Symlink | Omitted(_) => "   ",
     };
-    print!(
-        "[j/k] Move | [Enter/t] Toggle | [s] Sort({}) | [q] Quit | Index: {} > ",
-        sort_label, state.input_buffer
-    );
-    io::stdout().flush().ok();
-}
-
-/// 格式化文件大小
-fn format_size(size: u64) -> String {
-    if size < 1024 {
-        format!("{} B", size)
-    } else if size < 1024 * 1024 {
-        format!("{:.1} KB", size as f64 / 1024.0)
+
+    let size = item.node.size();
+    let mut size_str = if item.node.is_hardlink_duplicate() {
+        "hard link".to_string()
+    } else if item.node.size_is_partial() {
+        // 有条目因错误被跳过，大小只是下限
+        format!("≥{}", format_size(size, state.size_format))
     } else {
-        format!("{:.1} MB", size as f64 / 1024.0 / 1024.0)
+        format_size(size, state.size_format)
+    };
+    match state.column {
+        DisplayColumn::Size => {}
+        DisplayColumn::FileCount => {
+            if item.node.kind().is_dir() {
+                size_str = format!(
+                    "{} dirs, {} files, {}",
+                    item.node.descendant_dir_count(),
+                    item.node.descendant_file_count(),
+                    size_str
+                );
+            }
+        }
+        DisplayColumn::Mtime => {
+            size_str = item
+                .node
+                .latest_mtime()
+                .map_or_else(|| "-".to_string(), format_time);
+        }
+        DisplayColumn::ParentPercent => {
+            size_str = format!("{:.1}% of parent", item.fraction_of_parent() * 100.0);
+        }
+        DisplayColumn::Percent => {
+            let total = state.root.size();
+            let percent = if total == 0 {
+                0.0
+            } else {
+                size as f64 / total as f64 * 100.0
+            };
+            size_str = format!("{percent:.1}% of total");
+        }
+        DisplayColumn::Owner => {
+            if let Some(owner) = item.node.owner() {
+                size_str = format!(
+                    "{}:{} {}, {}",
+                    owner.uid(),
+                    owner.gid(),
+                    owner.permissions(),
+                    size_str
+                );
+            }
+        }
     }
+    // 重新扫描（或监视模式下的定时扫描）后大小变化的条目附加变化量
+    if let Some(&delta) = state.size_changes.get(item.node.path()) {
+        size_str = format!("{size_str}, {}", format_delta(delta, state.size_format));
+    }
+
+    // 渐变色：目录加粗，超过阈值的大文件使用醒目颜色
+    let name_color = if !state.color {
+        String::new()
+    } else {
+        match item.node.kind() {
+            Directory(_) => {
+                let color = gradient_color(
+                    size,
+                    ctx.dir_range,
+                    &state.theme.dir_gradient_start,
+                    &state.theme.dir_gradient_end,
+                    &state.theme.dir,
+                );
+                format!("{BOLD}{color}")
+            }
+            File if size >= state.large_file_threshold => ansi(state, &state.theme.large_file),
+            File | Symlink | Omitted(_) => gradient_color(
+                size,
+                ctx.file_range,
+                &state.theme.file_gradient_start,
+                &state.theme.file_gradient_end,
+                &state.theme.file,
+            ),
+        }
+    };
+    // 重新扫描后变大的条目与变小的条目分别着色
+    let size_color = if !state.color {
+        String::new()
+    } else if let Some(&delta) = state.size_changes.get(item.node.path()) {
+        if delta > 0 {
+            ansi(state, &state.theme.grown)
+        } else {
+            ansi(state, &state.theme.shrunk)
+        }
+    } else {
+        magnitude_color(
+            size,
+            ctx.size_max,
+            &state.theme.size_gradient_start,
+            &state.theme.size_gradient_end,
+        )
+    };
+    let fg_reset = ansi(state, &state.theme.fg_reset);
+    let name_reset = if state.color && item.node.kind().is_dir() {
+        format!("{fg_reset}{NORMAL_INTENSITY}")
+    } else {
+        fg_reset.clone()
+    };
+    // 符号链接名后附加 `@`，与 `ls -F` 一致
+    let link_marker = if let Symlink = item.node.kind() {
+        "@"
+    } else {
+        ""
+    };
+
+    let name = display_name(item.node.path());
+    let name = match name_limit {
+        Some(limit) => truncate_middle(&name, limit).into_owned(),
+        None => name.into_owned(),
+    };
+
+    format!(
+        "{} {}{}{}{} ({}{}{})",
+        icon, name_color, name, link_marker, name_reset, size_color, size_str, fg_reset,
+    )
+}
+
+/// 文本在终端中占用的列数：跳过 ANSI 转义序列，每个字符按一列计算
+fn visible_width(text: &str) -> usize {
+    let mut width = 0;
+    let mut in_escape = false;
+    for ch in text.chars() {
+        if in_escape {
+            in_escape = !ch.is_ascii_alphabetic();
+        } else if ch == '\x1b' {
+            in_escape = true;
+        } else {
+            width += 1;
+        }
+    }
+    width
 }
 
 /// 计算大小范围
@@ -184,29 +670,47 @@ fn size_range(view: &[ViewItem<'_>], want_dir: bool) -> Option<(u64, u64)> {
     min.zip(max)
 }
 
+/// 主题颜色的ANSI转义码，禁用颜色时为空
+fn ansi(state: &UiState, color: &Color) -> String {
+    if state.color {
+        color.to_ansi().unwrap_or_default()
+    } else {
+        String::new()
+    }
+}
+
 /// 计算渐变色
 fn gradient_color(
     size: u64,
-    min: u64,
-    max: u64,
+    (min, max): (u64, u64),
     start: &Color,
     end: &Color,
     fallback: &Color,
 ) -> String {
-    let start_rgb = start.to_rgb().ok();
-    let end_rgb = end.to_rgb().ok();
+    let t = if max <= min {
+        0.0
+    } else {
+        (size - min) as f64 / (max - min) as f64
+    };
+    gradient_at(t, start, end).unwrap_or_else(|| fallback.to_ansi().unwrap_or_default())
+}
 
-    if let (Some(s), Some(e)) = (start_rgb, end_rgb) {
-        let t = if max <= min {
-            0.0
-        } else {
-            (size - min) as f64 / (max - min) as f64
-        };
-        let (r, g, b) = lerp_rgb(s, e, t);
-        return format!("\x1b[38;2;{};{};{}m", r, g, b);
-    }
+/// 按数量级计算渐变色（对数刻度），使 KB 与 GB 级的条目颜色可区分
+fn magnitude_color(size: u64, max: u64, start: &Color, end: &Color) -> String {
+    let t = if max == 0 {
+        0.0
+    } else {
+        (size as f64).ln_1p() / (max as f64).ln_1p()
+    };
+    gradient_at(t, start, end).unwrap_or_default()
+}
 
-    fallback.to_ansi().unwrap_or_default()
+/// 渐变中 `t` 处的颜色，起止颜色无法转换为RGB时返回 `None`
+fn gradient_at(t: f64, start: &Color, end: &Color) -> Option<String> {
+    let s = start.to_rgb().ok()?;
+    let e = end.to_rgb().ok()?;
+    let (r, g, b) = lerp_rgb(s, e, t);
+    Some(format!("\x1b[38;2;{};{};{}m", r, g, b))
 }
 
 /// RGB线性插值
@@ -216,29 +720,120 @@ fn lerp_rgb(s: (u8, u8, u8), e: (u8, u8, u8), t: f64) -> (u8, u8, u8) {
     (lerp(s.0, e.0), lerp(s.1, e.1), lerp(s.2, e.2))
 }
 
-/// 读取用户输入
-pub fn get_input() -> anyhow::Result<Action> {
+/// 读取用户输入（按键与鼠标）；指定 `timeout` 时超时仍未输入返回 `None`
+pub fn get_input(state: &UiState, timeout: Option<Duration>) -> anyhow::Result<Option<Action>> {
+    let deadline = timeout.map(|timeout| Instant::now() + timeout);
     loop {
-        if let Event::Key(key) = event::read()? {
-            if key.kind == KeyEventKind::Release {
-                continue;
-            }
+        if let Some(deadline) = deadline
+            && !event::poll(deadline.saturating_duration_since(Instant::now()))?
+        {
+            return Ok(None);
+        }
+        let action = match event::read()? {
+            Event::Key(key) => key_action(&state.keys, state.input_mode, key),
+            Event::Mouse(mouse) => mouse_action(state, mouse),
+            _ => None,
+        };
+        if let Some(action) = action {
+            return Ok(Some(action));
+        }
+    }
+}
 
-            use KeyCode::*;
-            match key.code {
-                Up | Char('k' | 'K') => return Ok(Action::MoveUp),
-                Down | Char('j' | 'J') => return Ok(Action::MoveDown),
-                Enter => return Ok(Action::Enter),
-                Backspace => return Ok(Action::InputBackspace),
-                Char('q' | 'Q') => return Ok(Action::Quit),
-                Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                    return Ok(Action::Quit);
-                }
-                Char('t' | 'T') => return Ok(Action::ToggleAtCursor),
-                Char('s' | 'S') => return Ok(Action::ToggleSort),
-                Char(ch) if ch.is_ascii_digit() => return Ok(Action::InputDigit(ch)),
-                _ => {}
+/// 将鼠标事件映射为动作：普通模式下点击目录图标切换展开状态，点击行的其他位置移动光标，滚轮上下移动光标
+///
+/// 点击的行按最近一次渲染时的滚动偏移对应到可见列表中的项；其他模式下忽略鼠标。
+pub fn mouse_action(state: &UiState, mouse: MouseEvent) -> Option<Action> {
+    if state.input_mode != InputMode::Normal {
+        return None;
+    }
+    match mouse.kind {
+        MouseEventKind::ScrollUp => Some(Action::MoveUp),
+        MouseEventKind::ScrollDown => Some(Action::MoveDown),
+        MouseEventKind::Down(MouseButton::Left) => {
+            let row = usize::from(mouse.row).checked_sub(TREE_TOP_ROW)?;
+            if row >= state.viewport_height.max(1) {
+                return None;
+            }
+            let index = state.scroll_offset + row;
+            let item = state.flatten_window(index, 1).into_iter().next()?;
+            let icon = icon_column(state, item.depth);
+            if item.node.kind().is_dir() && (icon..icon + 3).contains(&usize::from(mouse.column)) {
+                Some(Action::ToggleRow(index))
+            } else {
+                Some(Action::Select(index))
             }
         }
+        _ => None,
+    }
+}
+
+/// 深度为 `depth` 的行中图标（`[+]` 等）的起始列，与 [`render`] 中每行的布局一致：
+/// 选中标记、标记符号、空格、目录编号、空格、比例条（含百分比）、空格、缩进
+fn icon_column(state: &UiState, depth: usize) -> usize {
+    let id_width = state.max_dir_id().to_string().len();
+    2 + 1 + id_width + 1 + (BAR_WIDTH + 9) + 1 + depth * 2
+}
+
+/// 将按键映射为当前输入模式下的动作，不对应任何动作的按键（包括按键释放）返回 `None`
+///
+/// 普通模式下按 `keys` 查找绑定的动作，数字键与退格固定用于输入目录编号。只做映射，不读取终端，也不修改状态。
+pub fn key_action(keys: &KeyMap, mode: InputMode, key: KeyEvent) -> Option<Action> {
+    if key.kind == KeyEventKind::Release {
+        return None;
+    }
+
+    use KeyCode::*;
+    if mode == InputMode::Confirm {
+        return Some(Action::Confirm(matches!(key.code, Char('y' | 'Y'))));
+    }
+    if key.code == Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+        return Some(Action::Quit);
+    }
+    if mode == InputMode::Move {
+        return match key.code {
+            Enter => Some(Action::Enter),
+            Backspace => Some(Action::InputBackspace),
+            Esc => Some(Action::ClearSearch),
+            Char(ch) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Action::InputChar(ch))
+            }
+            _ => None,
+        };
+    }
+    if mode == InputMode::Jump {
+        return match key.code {
+            Enter => Some(Action::Enter),
+            Backspace => Some(Action::InputBackspace),
+            Esc => Some(Action::ClearSearch),
+            Up => Some(Action::MoveUp),
+            Down => Some(Action::MoveDown),
+            Char(ch) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                Some(Action::InputChar(ch))
+            }
+            _ => None,
+        };
+    }
+    if mode == InputMode::Search {
+        return match key.code {
+            Enter => Some(Action::Enter),
+            Backspace => Some(Action::InputBackspace),
+            Esc => Some(Action::ClearSearch),
+            Up => Some(Action::MoveUp),
+            Down => Some(Action::MoveDown),
+            PageUp => Some(Action::PageUp),
+            PageDown => Some(Action::PageDown),
+            Char(ch) => Some(Action::InputChar(ch)),
+            _ => None,
+        };
+    }
+
+    if let Some(action) = keys.action(&key) {
+        return Some(action);
+    }
+    match key.code {
+        Backspace => Some(Action::InputBackspace),
+        Char(ch) if ch.is_ascii_digit() => Some(Action::InputDigit(ch)),
+        _ => None,
     }
 }
@@ -2,29 +2,122 @@
 //!
 //! 本模块提供了 [`UiState`]，用于跟踪节点的展开状态，并将层次树结构投影到线性列表中以便渲染。
 
-use crate::model::{Node, NodeKind::*};
+use crate::format::{SizeFormat, display_name, format_size};
+use crate::keys::KeyMap;
+use crate::model::{DirProperty, Node, NodeKind::*, ScanOptions, SortOrder};
 use crate::theme::Theme;
 use anyhow::bail;
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
 
-/// UI动作
-#[allow(dead_code)]
+/// 默认大文件高亮阈值：100 MiB
+pub const DEFAULT_LARGE_FILE_THRESHOLD: u64 = 100 * 1024 * 1024;
+
+/// UI动作：按键由 [`key_action`](crate::key_action) 映射为动作，再交给界面状态处理
+///
+/// 搜索是一种输入模式而不是单个动作：[`StartSearch`](Action::StartSearch) 进入搜索模式，
+/// 之后每个 [`InputChar`](Action::InputChar) 实时更新过滤结果，[`Enter`](Action::Enter)
+/// 保留结果返回普通模式，[`ClearSearch`](Action::ClearSearch) 清除搜索。
+/// 因此没有携带完整关键字的 `Search(String)`，动作也得以保持 `Copy`。
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
 pub enum Action {
-    Toggle(usize),      // 按索引切换
-    ToggleAtCursor,     // 切换光标处
-    MoveUp,             // 上移
-    MoveDown,           // 下移
-    Enter,              // 确认
-    InputDigit(char),   // 输入数字
-    InputBackspace,     // 退格
-    ToggleSort,         // 切换排序
-    Quit,               // 退出
-}
-
-/// 排序模式
+    Toggle(usize),    // 按目录编号切换
+    ToggleAtCursor,   // 切换光标处
+    Expand,           // 展开光标处
+    Collapse,         // 折叠光标处
+    MoveUp,           // 上移
+    MoveDown,         // 下移
+    PageUp,           // 上翻一页
+    PageDown,         // 下翻一页
+    Enter,            // 确认
+    InputDigit(char), // 输入数字
+    InputBackspace,   // 退格
+    ToggleSort,       // 切换排序
+    SortDirectory,    // 按当前排序方式重排光标处目录
+    ToggleFileCount,  // 切换文件数显示
+    ToggleRelative,   // 在大小、占父目录百分比与占总大小百分比之间切换
+    CycleColumn,      // 切换行尾显示的信息
+    ExpandAll,        // 全部展开
+    CollapseAll,      // 全部折叠（保留根节点）
+    StartSearch,      // 进入搜索模式
+    InputChar(char),  // 输入搜索字符
+    ClearSearch,      // 清除搜索
+    Rescan,           // 重新扫描光标处目录
+    Delete,           // 请求删除光标处节点
+    Trash,            // 将光标处节点移到回收站（无需确认）
+    Yank,             // 复制光标处节点的绝对路径到剪贴板
+    ToggleMark,       // 标记/取消标记光标处节点
+    DeleteMarked,     // 请求删除所有已标记节点
+    Confirm(bool),    // 回答确认提示
+    ToggleMinSize,    // 切换最小显示大小过滤
+    JumpToParent,     // 跳到光标处节点的父目录
+    Open,             // 在系统文件管理器中打开光标处节点
+    ToggleHidden,     // 切换隐藏条目的显示
+    ToggleDirsOnly,   // 切换只显示目录
+    ToggleBreakdown,  // 切换光标处目录的空间分布条
+    StartJump,        // 打开模糊跳转
+    Select(usize),    // 将光标移到可见列表中的指定项（鼠标点击名称）
+    ToggleRow(usize), // 选中并切换可见列表中的指定目录（鼠标点击图标）
+    ToggleFocus,      // 聚焦光标处节点（折叠其余分支）/恢复聚焦前的展开状态
+    StartMove,        // 输入目标目录，移动光标处节点
+    Quit,             // 退出
+}
+
+/// 行尾显示的信息
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum DisplayColumn {
+    /// 大小
+    #[default]
+    Size,
+    /// 目录的后代目录数与文件数，以及大小
+    FileCount,
+    /// 最近修改时间（目录取子树中最新的）
+    Mtime,
+    /// 占父目录大小的百分比
+    ParentPercent,
+    /// 占总大小的百分比
+    Percent,
+    /// 所有者的用户 ID、组 ID 与权限（仅 Unix）
+    Owner,
+}
+
+impl DisplayColumn {
+    /// 循环切换到下一项
+    pub fn next(self) -> Self {
+        match self {
+            DisplayColumn::Size => DisplayColumn::FileCount,
+            DisplayColumn::FileCount => DisplayColumn::Mtime,
+            DisplayColumn::Mtime => DisplayColumn::ParentPercent,
+            DisplayColumn::ParentPercent => DisplayColumn::Percent,
+            DisplayColumn::Percent => DisplayColumn::Owner,
+            DisplayColumn::Owner => DisplayColumn::Size,
+        }
+    }
+
+    /// 帮助栏中显示的名称
+    pub fn label(self) -> &'static str {
+        match self {
+            DisplayColumn::Size => "size",
+            DisplayColumn::FileCount => "counts",
+            DisplayColumn::Mtime => "mtime",
+            DisplayColumn::ParentPercent => "parent %",
+            DisplayColumn::Percent => "percent",
+            DisplayColumn::Owner => "owner",
+        }
+    }
+}
+
+/// 输入模式
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
-pub enum SortMode {
-    NameAsc,  // 按名称升序
-    SizeDesc, // 按大小降序
+pub enum InputMode {
+    Normal,  // 普通导航
+    Search,  // 输入搜索关键字
+    Confirm, // 等待确认（y/n）
+    Jump,    // 输入模糊跳转的查询
+    Move,    // 输入移动的目标目录
 }
 
 /// 状态消息
@@ -38,67 +131,355 @@ pub struct StatusMessage {
 pub struct ViewItem<'a> {
     pub node: &'a Node,
     pub depth: usize,
+    pub parent_size: u64, // 父目录大小，根节点为自身大小
+}
+
+impl ViewItem<'_> {
+    /// 节点大小占父目录的比例（0.0 ~ 1.0）
+    pub fn fraction_of_parent(&self) -> f64 {
+        if self.depth == 0 {
+            1.0
+        } else if self.parent_size == 0 {
+            0.0
+        } else {
+            self.node.size() as f64 / self.parent_size as f64
+        }
+    }
 }
 
 /// UI状态
 pub struct UiState<'a> {
-    pub root: &'a Node,
-    pub expanded_nodes: Vec<&'a Node>, // 已展开节点
-    pub cursor: usize,                 // 光标位置
-    pub viewport_height: usize,        // 视口高度
-    pub input_buffer: String,          // 输入缓冲
-    pub status: Option<StatusMessage>, // 状态消息
-    pub theme: Theme,                  // 主题
-    pub sort_mode: SortMode,           // 排序模式
+    pub root: &'a mut Node,
+    pub root_path: PathBuf,                  // 根目录的绝对路径（用于标题）
+    pub expanded_nodes: HashSet<PathBuf>,    // 已展开节点的路径
+    pub cursor: usize,                       // 光标位置
+    pub viewport_height: usize,              // 视口高度
+    pub viewport_width: usize,               // 视口宽度（列数）
+    pub scroll_offset: usize,                // 视口首行在可见列表中的索引
+    pub input_buffer: String,                // 输入缓冲
+    pub status: Option<StatusMessage>,       // 状态消息
+    pub theme: Theme,                        // 主题
+    pub sort_mode: SortOrder,                // 排序模式
+    pub group_dirs_first: bool,              // 排序时目录是否优先于文件
+    pub column: DisplayColumn,               // 行尾显示的信息
+    pub input_mode: InputMode,               // 输入模式
+    pub search_query: String,                // 搜索关键字，为空表示不过滤
+    pub color: bool,                         // 是否输出颜色
+    pub large_file_threshold: u64,           // 大文件高亮阈值（字节）
+    pub size_format: SizeFormat,             // 大小的显示方式（进制与小数位数）
+    pub pending_delete: Vec<PathBuf>,        // 等待确认删除的路径
+    pub min_size: u64,                       // 最小显示大小（字节），0 表示不限
+    pub hide_small: bool,                    // 是否隐藏小于最小显示大小的条目
+    pub interrupted: bool,                   // 扫描是否被中断（文件树不完整）
+    pub dir_ids: HashMap<PathBuf, usize>,    // 目录的稳定编号，展开/折叠后保持不变
+    pub marked: HashSet<PathBuf>,            // 已标记、等待批量操作的路径
+    pub show_hidden: bool,                   // 是否显示隐藏条目（名称以 `.` 开头）
+    pub dirs_only: bool,                     // 是否只显示目录
+    pub size_changes: HashMap<PathBuf, i64>, // 与上一次扫描相比大小变化的条目（监视模式与重新扫描）
+    pub show_breakdown: bool,                // 是否显示光标处目录的空间分布条
+    pub keys: KeyMap,                        // 普通模式下的按键绑定
+    pub scanning: Option<usize>,             // 后台扫描已扫描的项数，没有进行中的扫描时为 None
+    pub jump_query: String,                  // 模糊跳转的查询
+    pub jump_selected: usize,                // 模糊跳转中选中的匹配项
+    pub focus_snapshot: Option<HashSet<PathBuf>>, // 聚焦前的展开状态，未聚焦时为 None
+    pub move_target: String,                 // 移动的目标目录
+    pub scan_options: ScanOptions,           // 扫描选项，重新扫描子树时沿用
+    next_dir_id: usize,                      // 下一个新目录的编号
 }
 
 impl<'a> UiState<'a> {
     /// 创建新状态，默认展开根节点
-    pub fn new(root: &'a Node, theme: Theme) -> Self {
-        Self {
+    pub fn new(root: &'a mut Node, theme: Theme) -> Self {
+        // 合成根目录的占位路径不对应实际目录
+        let root_path = if root.is_multi_root() {
+            root.path().to_path_buf()
+        } else {
+            std::path::absolute(root.path()).unwrap_or_else(|_| root.path().into())
+        };
+        let mut state = Self {
+            root_path,
+            expanded_nodes: HashSet::from([root.path().to_path_buf()]),
             root,
-            expanded_nodes: vec![root],
             cursor: 0,
             viewport_height: 20,
+            viewport_width: 80,
+            scroll_offset: 0,
             input_buffer: String::new(),
             status: None,
             theme,
-            sort_mode: SortMode::SizeDesc,
+            sort_mode: SortOrder::SizeDesc,
+            group_dirs_first: true,
+            column: DisplayColumn::Size,
+            input_mode: InputMode::Normal,
+            search_query: String::new(),
+            color: true,
+            large_file_threshold: DEFAULT_LARGE_FILE_THRESHOLD,
+            size_format: SizeFormat::default(),
+            pending_delete: Vec::new(),
+            min_size: 0,
+            hide_small: true,
+            interrupted: false,
+            dir_ids: HashMap::new(),
+            next_dir_id: 0,
+            marked: HashSet::new(),
+            show_hidden: true,
+            dirs_only: false,
+            size_changes: HashMap::new(),
+            show_breakdown: false,
+            keys: KeyMap::default(),
+            scanning: None,
+            jump_query: String::new(),
+            jump_selected: 0,
+            focus_snapshot: None,
+            move_target: String::new(),
+            scan_options: ScanOptions::default(),
+        };
+        state.assign_dir_ids();
+        state
+    }
+
+    /// 为尚未编号的目录分配编号，并移除树中已不存在的目录的编号
+    ///
+    /// 按层序、以当前排序方式编号，初始视图中根目录的子目录编号连续；已有编号的目录保持不变，
+    /// 编号也不会被重复使用。
+    fn assign_dir_ids(&mut self) {
+        let mut seen = HashSet::new();
+        let mut queue = VecDeque::from([&*self.root]);
+        while let Some(node) = queue.pop_front() {
+            let Directory(prop) = node.kind() else {
+                continue;
+            };
+            seen.insert(node.path());
+            if !self.dir_ids.contains_key(node.path()) {
+                self.dir_ids
+                    .insert(node.path().to_path_buf(), self.next_dir_id);
+                self.next_dir_id += 1;
+            }
+            let mut children: Vec<&Node> = prop
+                .children()
+                .iter()
+                .filter(|child| child.kind().is_dir())
+                .collect();
+            children.sort_by(|a, b| self.sort_mode.compare(a, b, self.group_dirs_first));
+            queue.extend(children);
         }
+        self.dir_ids.retain(|path, _| seen.contains(path.as_path()));
+    }
+
+    /// 目录的稳定编号，文件与符号链接返回 `None`
+    pub fn dir_id(&self, node: &Node) -> Option<usize> {
+        self.dir_ids.get(node.path()).copied()
+    }
+
+    /// 已分配的最大目录编号
+    pub fn max_dir_id(&self) -> usize {
+        self.next_dir_id.saturating_sub(1)
+    }
+
+    /// 编号为 `id` 的目录在可见列表中的索引
+    fn index_of_dir_id(&self, id: usize) -> anyhow::Result<usize> {
+        let path = self
+            .dir_ids
+            .iter()
+            .find_map(|(path, &dir_id)| (dir_id == id).then_some(path))
+            .ok_or_else(|| anyhow::anyhow!("Directory {id} not found!"))?;
+        self.flatten_view()
+            .iter()
+            .position(|item| item.node.path() == path)
+            .ok_or_else(|| anyhow::anyhow!("Directory {id} is not visible"))
+    }
+
+    /// 按编号切换目录展开/折叠，并将光标移到该目录
+    fn toggle_by_dir_id(&mut self, id: usize) -> anyhow::Result<()> {
+        let index = self.index_of_dir_id(id)?;
+        self.toggle_by_index(index)?;
+        self.cursor = index;
+        Ok(())
     }
 
     /// 展平树为可见项列表
-    pub fn flatten_view(&self) -> Vec<ViewItem<'a>> {
+    ///
+    /// 搜索关键字非空时忽略展开状态，只保留名称包含关键字（不区分大小写）的节点及其祖先目录。
+    pub fn flatten_view(&self) -> Vec<ViewItem<'_>> {
         let mut items = Vec::new();
-        self.collect_recursive(self.root, 0, &mut items);
+        if self.search_query.is_empty() {
+            self.collect_recursive(self.root, 0, self.root.size(), &mut items);
+        } else {
+            let query = self.search_query.to_lowercase();
+            if !self.collect_matching(self.root, 0, self.root.size(), &query, &mut items) {
+                // 没有匹配项时仍显示根节点
+                items.push(ViewItem {
+                    node: self.root,
+                    depth: 0,
+                    parent_size: self.root.size(),
+                });
+            }
+        }
         items
     }
 
+    /// 只展平从第 `offset` 项开始的至多 `limit` 项，收集足够后不再遍历剩余的树
+    ///
+    /// 结果与 `flatten_view()` 的对应切片相同；搜索模式下需要遍历整棵树判断匹配，因此退化为切片。
+    pub fn flatten_window(&self, offset: usize, limit: usize) -> Vec<ViewItem<'_>> {
+        if !self.search_query.is_empty() {
+            return self
+                .flatten_view()
+                .into_iter()
+                .skip(offset)
+                .take(limit)
+                .collect();
+        }
+        let mut items = Vec::with_capacity(limit);
+        let mut skip = offset;
+        self.collect_window(self.root, 0, self.root.size(), &mut skip, limit, &mut items);
+        items
+    }
+
+    /// 可见项总数，不分配列表，也不对子节点排序
+    pub fn visible_len(&self) -> usize {
+        if self.search_query.is_empty() {
+            self.count_visible(self.root)
+        } else {
+            self.flatten_view().len()
+        }
+    }
+
+    /// 节点是否通过显示过滤：达到最小显示大小，隐藏条目在关闭显示时不出现，只显示目录时不出现文件与符号链接
+    ///
+    /// 目录大小不小于其任何后代，因此包含达标后代的祖先目录总会保留；隐藏目录的后代随之一并隐藏。
+    /// 目录大小已包含文件，只显示目录时总计仍然准确。
+    fn is_shown(&self, node: &Node) -> bool {
+        (!self.hide_small || node.size() >= self.min_size)
+            && (self.show_hidden || !node.is_hidden())
+            && (!self.dirs_only || node.kind().is_dir())
+    }
+
+    /// 目录中通过显示过滤的子节点，按显示顺序排列
+    fn visible_children<'v>(&self, prop: &'v DirProperty) -> Vec<&'v Node> {
+        let mut children: Vec<&Node> = prop
+            .children()
+            .iter()
+            .filter(|child| self.is_shown(child))
+            .collect();
+        children.sort_by(|a, b| self.sort_mode.compare(a, b, self.group_dirs_first));
+        children
+    }
+
+    /// 递归收集匹配搜索关键字的节点，返回子树中是否存在匹配
+    fn collect_matching<'v>(
+        &self,
+        node: &'v Node,
+        depth: usize,
+        parent_size: u64,
+        query: &str,
+        items: &mut Vec<ViewItem<'v>>,
+    ) -> bool {
+        let start = items.len();
+        items.push(ViewItem {
+            node,
+            depth,
+            parent_size,
+        });
+
+        let mut matched = display_name(node.path()).to_lowercase().contains(query);
+
+        if let Directory(prop) = node.kind() {
+            for child in self.visible_children(prop) {
+                matched |= self.collect_matching(child, depth + 1, node.size(), query, items);
+            }
+        }
+
+        if !matched {
+            items.truncate(start);
+        }
+        matched
+    }
+
     /// 递归收集可见节点
-    fn collect_recursive(&self, node: &'a Node, depth: usize, items: &mut Vec<ViewItem<'a>>) {
-        items.push(ViewItem { node, depth });
+    fn collect_recursive<'v>(
+        &self,
+        node: &'v Node,
+        depth: usize,
+        parent_size: u64,
+        items: &mut Vec<ViewItem<'v>>,
+    ) {
+        items.push(ViewItem {
+            node,
+            depth,
+            parent_size,
+        });
 
         if let Directory(prop) = node.kind()
-            && self.expanded_nodes.contains(&node)
+            && self.is_expanded(node)
         {
-            let mut children: Vec<&Node> = prop.children().iter().collect();
-            children.sort_by(|a, b| self.compare_nodes(a, b));
-            for child in children {
-                self.collect_recursive(child, depth + 1, items);
+            for child in self.visible_children(prop) {
+                self.collect_recursive(child, depth + 1, node.size(), items);
             }
         }
     }
 
-    /// 比较节点（目录优先，再按排序模式）
-    fn compare_nodes(&self, a: &Node, b: &Node) -> std::cmp::Ordering {
-        match (a.kind(), b.kind()) {
-            (Directory(_), File) => std::cmp::Ordering::Less,
-            (File, Directory(_)) => std::cmp::Ordering::Greater,
-            _ => match self.sort_mode {
-                SortMode::NameAsc => a.path().cmp(b.path()),
-                SortMode::SizeDesc => b.size().cmp(&a.size()).then_with(|| a.path().cmp(b.path())),
-            },
+    /// 按窗口递归收集可见节点，先跳过 `skip` 项；收集满 `limit` 项时返回 `false` 以停止遍历
+    fn collect_window<'v>(
+        &self,
+        node: &'v Node,
+        depth: usize,
+        parent_size: u64,
+        skip: &mut usize,
+        limit: usize,
+        items: &mut Vec<ViewItem<'v>>,
+    ) -> bool {
+        if *skip > 0 {
+            *skip -= 1;
+        } else {
+            if items.len() >= limit {
+                return false;
+            }
+            items.push(ViewItem {
+                node,
+                depth,
+                parent_size,
+            });
+        }
+
+        if let Directory(prop) = node.kind()
+            && self.is_expanded(node)
+        {
+            for child in self.visible_children(prop) {
+                if !self.collect_window(child, depth + 1, node.size(), skip, limit, items) {
+                    return false;
+                }
+            }
         }
+        true
+    }
+
+    /// 统计子树中的可见节点数（含自身）
+    fn count_visible(&self, node: &Node) -> usize {
+        match node.kind() {
+            Directory(prop) if self.is_expanded(node) => {
+                1 + prop
+                    .children()
+                    .iter()
+                    .filter(|child| self.is_shown(child))
+                    .map(|child| self.count_visible(child))
+                    .sum::<usize>()
+            }
+            _ => 1,
+        }
+    }
+
+    /// 调整滚动偏移，使光标保持在视口内
+    pub fn scroll_to_cursor(&mut self) {
+        let total = self.visible_len();
+        let height = self.viewport_height.max(1);
+        if self.cursor < self.scroll_offset {
+            self.scroll_offset = self.cursor;
+        } else if self.cursor >= self.scroll_offset + height {
+            self.scroll_offset = self.cursor + 1 - height;
+        }
+        // 列表变短时不留空白
+        self.scroll_offset = self.scroll_offset.min(total.saturating_sub(height));
     }
 
     /// 移动光标
@@ -115,6 +496,15 @@ impl<'a> UiState<'a> {
         self.cursor = new_cursor;
     }
 
+    /// 光标处节点的父目录在可见列表中的索引：向前查找第一个深度更小的条目，根节点返回 `None`
+    fn parent_of_cursor(&self) -> Option<usize> {
+        let view = self.flatten_view();
+        let depth = view.get(self.cursor)?.depth;
+        view[..self.cursor]
+            .iter()
+            .rposition(|item| item.depth < depth)
+    }
+
     /// 切换光标处目录
     fn toggle_at_cursor(&mut self) -> anyhow::Result<()> {
         self.toggle_by_index(self.cursor)
@@ -128,42 +518,492 @@ impl<'a> UiState<'a> {
         });
     }
 
+    /// 设置提示消息
+    fn set_info(&mut self, message: impl Into<String>) {
+        self.status = Some(StatusMessage {
+            text: message.into(),
+            is_error: false,
+        });
+    }
+
     /// 清除状态消息
     fn clear_status(&mut self) {
         self.status = None;
     }
 
-    /// 按索引切换目录展开/折叠
-    fn toggle_by_index(&mut self, index: usize) -> anyhow::Result<()> {
-        let view = self.flatten_view();
-        let item = view
-            .get(index)
+    /// 获取索引处可展开的目录节点
+    fn directory_at(&self, index: usize) -> anyhow::Result<&Node> {
+        let target_node = self
+            .flatten_window(index, 1)
+            .first()
+            .map(|item| item.node)
             .ok_or_else(|| anyhow::anyhow!("Index {index} not found!"))?;
-        let target_node = item.node;
 
-        if let File = target_node.kind() {
-            bail!("Cannot toggle file");
+        match target_node.kind() {
+            File => bail!("Cannot toggle file"),
+            Symlink => bail!("Cannot toggle symlink"),
+            Omitted(_) => bail!("Cannot toggle omitted entries"),
+            Directory(prop) if prop.is_other_filesystem() => {
+                bail!("Directory is on another filesystem (not scanned)")
+            }
+            Directory(prop) if prop.is_truncated() => {
+                bail!("Directory contents not scanned (beyond max depth)")
+            }
+            Directory(_) => Ok(target_node),
         }
+    }
 
-        // 切换展开状态
-        match self.expanded_nodes.iter().position(|&x| x == target_node) {
-            Some(idx) => { self.expanded_nodes.remove(idx); }
-            None => { self.expanded_nodes.push(target_node); }
+    /// 节点是否已标记
+    pub fn is_marked(&self, node: &Node) -> bool {
+        self.marked.contains(node.path())
+    }
+
+    /// 已标记且仍在树中的最外层路径及其总大小
+    ///
+    /// 祖先也被标记的节点会随祖先一并删除，既不单独列出也不重复计入大小。
+    pub fn marked_roots(&self) -> (Vec<PathBuf>, u64) {
+        let mut paths: Vec<&PathBuf> = self
+            .marked
+            .iter()
+            .filter(|path| {
+                !self
+                    .marked
+                    .iter()
+                    .any(|other| other != *path && path.starts_with(other))
+            })
+            .collect();
+        paths.sort();
+        let mut total = 0;
+        let mut roots = Vec::with_capacity(paths.len());
+        for path in paths {
+            if let Some(node) = self.root.find(path) {
+                total += node.size();
+                roots.push(path.clone());
+            }
+        }
+        (roots, total)
+    }
+
+    /// 标记或取消标记光标处节点
+    fn toggle_mark_at_cursor(&mut self) -> anyhow::Result<()> {
+        let node = self.node_at_cursor()?;
+        if std::ptr::eq(node, &*self.root) {
+            bail!("Cannot mark the root directory");
+        }
+        if matches!(node.kind(), Omitted(_)) {
+            bail!("Cannot mark omitted entries");
+        }
+        let path = node.path().to_path_buf();
+        if !self.marked.remove(&path) {
+            self.marked.insert(path);
+        }
+        Ok(())
+    }
+
+    /// 目录是否已展开
+    pub fn is_expanded(&self, node: &Node) -> bool {
+        self.expanded_nodes.contains(node.path())
+    }
+
+    /// 设置目录的展开状态
+    fn set_expanded(&mut self, path: PathBuf, expanded: bool) {
+        if expanded {
+            self.expanded_nodes.insert(path);
+        } else {
+            self.expanded_nodes.remove(&path);
         }
+        self.clamp_cursor();
+    }
+
+    /// 展开深度不超过 `max_depth` 的所有目录（根目录深度为 0）
+    pub fn expand_to_depth(&mut self, max_depth: usize) {
+        expand_recursive_bounded(&mut self.expanded_nodes, self.root, 0, max_depth);
+    }
 
-        // 调整光标
-        let view_len = self.flatten_view().len();
+    /// 设置整棵树中所有目录的展开状态（未保留子节点的目录除外）
+    fn set_all_expanded(&mut self, expanded: bool) {
+        set_expanded_recursive(&mut self.expanded_nodes, self.root, expanded);
+    }
+
+    /// 可见列表变化后将光标限制在范围内
+    fn clamp_cursor(&mut self) {
+        let view_len = self.visible_len();
         if self.cursor >= view_len {
             self.cursor = view_len.saturating_sub(1);
         }
+    }
+
+    /// 按索引切换目录展开/折叠
+    fn toggle_by_index(&mut self, index: usize) -> anyhow::Result<()> {
+        let path = self.directory_at(index)?.path().to_path_buf();
+        let expanded = self.expanded_nodes.contains(&path);
+        self.set_expanded(path, !expanded);
+        Ok(())
+    }
+
+    /// 展开或折叠光标处目录，已处于目标状态时不做任何事
+    fn set_expanded_at_cursor(&mut self, expanded: bool) -> anyhow::Result<()> {
+        let path = self.directory_at(self.cursor)?.path().to_path_buf();
+        self.set_expanded(path, expanded);
+        Ok(())
+    }
+
+    /// 获取光标处节点
+    fn node_at_cursor(&self) -> anyhow::Result<&Node> {
+        self.flatten_window(self.cursor, 1)
+            .first()
+            .map(|item| item.node)
+            .ok_or_else(|| anyhow::anyhow!("Index {} not found!", self.cursor))
+    }
+
+    /// 模糊跳转中与查询匹配的节点，得分高者在前，得分相同时路径短者在前，最多 `limit` 项
+    ///
+    /// 候选为根节点以外所有通过显示过滤的节点（不论是否展开），以相对于根目录的路径匹配；查询为空时没有匹配。
+    pub fn jump_matches(&self, limit: usize) -> Vec<&Node> {
+        let query: Vec<char> = self.jump_query.to_lowercase().chars().collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut matches = Vec::new();
+        self.collect_jump_matches(self.root, &query, &mut matches);
+        matches.sort_unstable_by(|(a_score, a), (b_score, b)| {
+            b_score
+                .cmp(a_score)
+                .then_with(|| a.path().as_os_str().len().cmp(&b.path().as_os_str().len()))
+                .then_with(|| a.path().cmp(b.path()))
+        });
+        matches.truncate(limit);
+        matches.into_iter().map(|(_, node)| node).collect()
+    }
+
+    /// 递归收集子树中与模糊跳转查询匹配的节点及其得分
+    fn collect_jump_matches<'v>(
+        &self,
+        node: &'v Node,
+        query: &[char],
+        matches: &mut Vec<(i64, &'v Node)>,
+    ) {
+        if let Directory(prop) = node.kind() {
+            for child in prop.children().iter().filter(|child| self.is_shown(child)) {
+                let path = child.relative_path(self.root.path()).to_string_lossy();
+                if let Some(score) = fuzzy_score(query, &path) {
+                    matches.push((score, child));
+                }
+                self.collect_jump_matches(child, query, matches);
+            }
+        }
+    }
+
+    /// 展开 `path` 的所有上层目录并将光标移到该节点；搜索过滤会一并清除，保证节点可见
+    fn jump_to(&mut self, path: &Path) {
+        self.search_query.clear();
+        self.expand_ancestors(path);
+        self.move_cursor_to(path);
+    }
+
+    /// 展开 `path` 的所有上层目录（直到根目录）
+    fn expand_ancestors(&mut self, path: &Path) {
+        for ancestor in path.ancestors().skip(1) {
+            self.expanded_nodes.insert(ancestor.to_path_buf());
+            if ancestor == self.root.path() {
+                break;
+            }
+        }
+        // 多根目录的合成根不是扫描路径的上层目录
+        self.expanded_nodes.insert(self.root.path().to_path_buf());
+    }
+
+    /// 节点可见时将光标移到该节点，否则将光标限制在范围内
+    fn move_cursor_to(&mut self, path: &Path) {
+        match self
+            .flatten_view()
+            .iter()
+            .position(|item| item.node.path() == path)
+        {
+            Some(index) => self.cursor = index,
+            None => self.clamp_cursor(),
+        }
+    }
+
+    /// 聚焦光标处节点：折叠其他所有目录，只保留从根目录到该节点的路径（节点为目录时一并展开）；
+    /// 已聚焦时恢复聚焦前的展开状态
+    ///
+    /// 聚焦期间的展开与折叠在恢复时丢弃。
+    fn toggle_focus(&mut self) -> anyhow::Result<()> {
+        let node = self.node_at_cursor()?;
+        let path = node.path().to_path_buf();
+        let is_dir = node.kind().is_dir();
+        match self.focus_snapshot.take() {
+            Some(snapshot) => {
+                self.expanded_nodes = snapshot;
+                self.set_info("Restored the previous expansion");
+            }
+            None => {
+                self.focus_snapshot = Some(self.expanded_nodes.clone());
+                self.set_all_expanded(false);
+                if is_dir {
+                    self.expanded_nodes.insert(path.clone());
+                }
+                self.expand_ancestors(&path);
+                let key = self.keys.label(Action::ToggleFocus).to_string();
+                self.set_info(format!(
+                    "Focused on {}; press {key} again to restore",
+                    path.display()
+                ));
+            }
+        }
+        self.move_cursor_to(&path);
+        Ok(())
+    }
+
+    /// 空间分布条展示的目录：光标处为目录时即该目录，否则为其所在目录
+    pub fn breakdown_dir(&self) -> Option<&Node> {
+        let node = self.node_at_cursor().ok()?;
+        if node.kind().is_dir() {
+            return Some(node);
+        }
+        self.root.find(node.path().parent()?)
+    }
+
+    /// 将光标处目录的子节点按当前排序方式原地重排，返回目录路径
+    ///
+    /// 只影响这一层，子目录的展开状态保持不变。
+    fn sort_directory_at_cursor(&mut self) -> anyhow::Result<PathBuf> {
+        let path = self.directory_at(self.cursor)?.path().to_path_buf();
+        let node = self
+            .root
+            .find_mut(&path)
+            .ok_or_else(|| anyhow::anyhow!("{} not found", path.display()))?;
+        node.sort_children(self.sort_mode, self.group_dirs_first);
+        Ok(path)
+    }
+
+    /// 从磁盘删除节点，成功后从树中移除并更新祖先目录大小；失败时树保持不变
+    fn delete(&mut self, path: &Path) -> anyhow::Result<()> {
+        remove_path(path)?;
+        self.remove_from_tree(path);
         Ok(())
     }
 
+    /// 将光标处节点移到目录 `destination` 中（名称不变），返回新路径
+    ///
+    /// 相对路径相对于当前工作目录。目标目录中已有同名条目时不覆盖而是报错；跨文件系统时复制后删除原条目。
+    /// 成功后从原位置移除节点：目标目录在树中时（无论写作绝对路径还是相对路径）以新路径插入并更新沿途目录大小，
+    /// 否则节点离开文件树。
+    fn move_at_cursor(&mut self, destination: &str) -> anyhow::Result<PathBuf> {
+        let node = self.node_at_cursor()?;
+        if std::ptr::eq(node, &*self.root) || node.is_multi_root() {
+            bail!("Cannot move the root directory");
+        }
+        if matches!(node.kind(), Omitted(_)) {
+            bail!("Cannot move omitted entries");
+        }
+        let source = node.path().to_path_buf();
+        let Some(name) = source.file_name() else {
+            bail!("Cannot move {}", source.display());
+        };
+        let destination = destination.trim();
+        if destination.is_empty() {
+            bail!("No destination given");
+        }
+        let destination = normalize_path(Path::new(destination));
+        if !destination.is_dir() {
+            bail!("{} is not a directory", destination.display());
+        }
+        let target = destination.join(name);
+        if target.symlink_metadata().is_ok() {
+            bail!("{} already exists", target.display());
+        }
+        if destination
+            .canonicalize()?
+            .starts_with(source.canonicalize()?)
+        {
+            bail!("Cannot move {} into itself", source.display());
+        }
+
+        move_path(&source, &target)
+            .map_err(|e| anyhow::anyhow!("Cannot move {}: {e}", source.display()))?;
+        let moved = self.remove_from_tree(&source);
+        // 树中的路径与扫描时给出的根路径同形（可能是相对路径），按树中的写法查找目标目录
+        let tree_destination = self.tree_path(&destination);
+        let tree_target = tree_destination.as_ref().map(|dir| dir.join(name));
+        match (
+            moved,
+            tree_destination
+                .as_deref()
+                .and_then(|dir| self.root.find(dir))
+                .map(Node::kind),
+        ) {
+            (Some(mut node), Some(Directory(prop))) if !prop.is_truncated() => {
+                node.relocate(tree_target.clone().unwrap_or_else(|| target.clone()));
+                self.root.insert_descendant(node);
+            }
+            // 未保留子节点的目录无法插入，重新扫描以更新大小；失败时保持原有大小
+            (_, Some(Directory(_))) => {
+                if let Some(dir) = &tree_destination {
+                    let _ = self.root.rescan_subtree(dir, &self.scan_options);
+                }
+            }
+            _ => {}
+        }
+        self.assign_dir_ids();
+        if let Some(tree_target) = &tree_target {
+            self.move_cursor_to(tree_target);
+        }
+        Ok(target)
+    }
+
+    /// 将绝对路径（见 [`normalize_path`]）转换为树中的写法，不在任何扫描根目录之下时返回 `None`
+    ///
+    /// 扫描根目录为相对路径时，树中的路径也都是相对路径，需要转换后才能用 [`Node::find`] 查找。
+    fn tree_path(&self, path: &Path) -> Option<PathBuf> {
+        let roots = if self.root.is_multi_root() {
+            match self.root.kind() {
+                Directory(prop) => prop.children().iter().map(Node::path).collect(),
+                _ => Vec::new(),
+            }
+        } else {
+            vec![self.root.path()]
+        };
+        roots.into_iter().find_map(|root| {
+            let relative = path.strip_prefix(normalize_path(root)).ok()?;
+            Some(if relative.as_os_str().is_empty() {
+                root.to_path_buf()
+            } else {
+                root.join(relative)
+            })
+        })
+    }
+
+    /// 将光标处节点移到系统回收站，成功后从树中移除并更新祖先目录大小，返回其路径
+    fn trash_at_cursor(&mut self) -> anyhow::Result<PathBuf> {
+        let node = self.node_at_cursor()?;
+        if std::ptr::eq(node, &*self.root) {
+            bail!("Cannot trash the root directory");
+        }
+        if matches!(node.kind(), Omitted(_)) {
+            bail!("Cannot trash omitted entries");
+        }
+        let path = node.path().to_path_buf();
+        move_to_trash(&path)?;
+        self.remove_from_tree(&path);
+        Ok(path)
+    }
+
+    /// 复制光标处节点的绝对路径到剪贴板，返回要显示的提示
+    ///
+    /// 剪贴板不可用（如没有图形界面）时不报错，而是在提示中给出路径。
+    fn yank_at_cursor(&self) -> anyhow::Result<String> {
+        let node = self.node_at_cursor()?;
+        if node.is_multi_root() {
+            bail!("Cannot copy the combined root");
+        }
+        let path = std::path::absolute(node.path())?;
+        Ok(match copy_to_clipboard(&path.to_string_lossy()) {
+            Ok(()) => format!("Copied: {}", path.display()),
+            Err(e) => format!("{e}; path: {}", path.display()),
+        })
+    }
+
+    /// 从树中移除已不在磁盘上的节点，并清理与之相关的界面状态，返回被移除的节点
+    fn remove_from_tree(&mut self, path: &Path) -> Option<Node> {
+        let removed = self.root.remove_descendant(path);
+        self.expanded_nodes.retain(|p| !p.starts_with(path));
+        self.dir_ids.retain(|p, _| !p.starts_with(path));
+        self.marked.retain(|p| !p.starts_with(path));
+        removed
+    }
+
+    /// 用同一路径重新扫描得到的文件树替换当前的树，并记录大小变化的条目
+    ///
+    /// 仍存在的目录保持展开状态与编号，已消失条目的标记与单独排序被清除。
+    pub fn replace_tree(&mut self, node: Node) {
+        self.size_changes = node.size_changes(self.root);
+        *self.root = node;
+        let root = &*self.root;
+        self.expanded_nodes.retain(|p| root.find(p).is_some());
+        self.marked.retain(|p| root.find(p).is_some());
+        self.assign_dir_ids();
+        self.clamp_cursor();
+    }
+
+    /// 将后台扫描完成的子树加入文件树，光标保持在原来的条目上
+    pub fn insert_subtrees(&mut self, nodes: impl IntoIterator<Item = Node>) {
+        let selected = self.node_at_cursor().ok().map(|n| n.path().to_path_buf());
+        for node in nodes {
+            self.root.insert_descendant(node);
+        }
+        self.assign_dir_ids();
+        if let Some(index) = selected.and_then(|path| {
+            self.flatten_view()
+                .iter()
+                .position(|item| item.node.path() == path)
+        }) {
+            self.cursor = index;
+        }
+        self.clamp_cursor();
+    }
+
+    /// 重新扫描光标处目录，返回其路径；其余节点的展开状态保持不变
+    fn rescan_at_cursor(&mut self) -> anyhow::Result<PathBuf> {
+        let node = self.node_at_cursor()?;
+        if !node.kind().is_dir() {
+            bail!("Cannot rescan non-directory");
+        }
+        if node.is_multi_root() {
+            bail!("Cannot rescan the combined root; rescan each path instead");
+        }
+        let path = node.path().to_path_buf();
+        let previous = node.clone();
+        self.root.rescan_subtree(&path, &self.scan_options)?;
+        // 只有该子树被替换，所有上层目录的变化量都与子树根相同
+        if let Some(fresh) = self.root.find(&path) {
+            self.size_changes = fresh.size_changes(&previous);
+            if let Some(&delta) = self.size_changes.get(&path) {
+                let root = &*self.root;
+                let ancestors = path.ancestors().skip(1).filter(|a| root.find(a).is_some());
+                // 多根目录的合成根不是扫描路径的上层目录，单独记录
+                for ancestor in ancestors.chain([root.path()]) {
+                    self.size_changes.insert(ancestor.to_path_buf(), delta);
+                }
+            }
+        }
+        self.assign_dir_ids();
+        Ok(path)
+    }
+
     /// 处理动作，返回是否继续运行
     pub fn update(&mut self, action: Action) -> anyhow::Result<bool> {
-        let view_len = self.flatten_view().len();
+        let view_len = self.visible_len();
+
+        // 扫描结束后文件树会被整体替换，期间不修改磁盘或重新扫描
+        if self.scanning.is_some()
+            && matches!(
+                action,
+                Action::Rescan
+                    | Action::Delete
+                    | Action::DeleteMarked
+                    | Action::Trash
+                    | Action::StartMove
+            )
+        {
+            self.input_buffer.clear();
+            self.set_error("Not available until the scan finishes");
+            return Ok(true);
+        }
 
         match action {
+            Action::MoveUp if self.input_mode == InputMode::Jump => {
+                self.jump_selected = self.jump_selected.saturating_sub(1);
+                Ok(true)
+            }
+            Action::MoveDown if self.input_mode == InputMode::Jump => {
+                let count = self.jump_matches(self.viewport_height.max(1)).len();
+                if self.jump_selected + 1 < count {
+                    self.jump_selected += 1;
+                }
+                Ok(true)
+            }
             Action::MoveUp => {
                 self.input_buffer.clear();
                 self.clear_status();
@@ -176,6 +1016,18 @@ impl<'a> UiState<'a> {
                 self.move_cursor(1, view_len);
                 Ok(true)
             }
+            Action::PageUp | Action::PageDown => {
+                self.input_buffer.clear();
+                self.clear_status();
+                let page = self.viewport_height.max(1) as isize;
+                let delta = if matches!(action, Action::PageUp) {
+                    -page
+                } else {
+                    page
+                };
+                self.move_cursor(delta, view_len);
+                Ok(true)
+            }
             Action::ToggleAtCursor => {
                 self.input_buffer.clear();
                 match self.toggle_at_cursor() {
@@ -184,14 +1036,92 @@ impl<'a> UiState<'a> {
                 }
                 Ok(true)
             }
-            Action::Toggle(index) => {
+            Action::Expand | Action::Collapse => {
+                self.input_buffer.clear();
+                let expanded = matches!(action, Action::Expand);
+                match self.set_expanded_at_cursor(expanded) {
+                    Ok(()) => self.clear_status(),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::Select(index) => {
+                self.input_buffer.clear();
+                self.clear_status();
+                if index < view_len {
+                    self.cursor = index;
+                }
+                Ok(true)
+            }
+            Action::ToggleRow(index) => {
                 self.input_buffer.clear();
+                if index < view_len {
+                    self.cursor = index;
+                }
                 match self.toggle_by_index(index) {
-                    Ok(()) => {
-                        self.cursor = index.min(self.flatten_view().len().saturating_sub(1));
+                    Ok(()) => self.clear_status(),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::JumpToParent => {
+                self.input_buffer.clear();
+                self.clear_status();
+                if let Some(parent) = self.parent_of_cursor() {
+                    self.cursor = parent;
+                }
+                Ok(true)
+            }
+            Action::ExpandAll => {
+                self.input_buffer.clear();
+                self.clear_status();
+                self.set_all_expanded(true);
+                Ok(true)
+            }
+            Action::CollapseAll => {
+                self.input_buffer.clear();
+                self.clear_status();
+                self.set_all_expanded(false);
+                self.expanded_nodes.insert(self.root.path().to_path_buf());
+                self.cursor = 0;
+                Ok(true)
+            }
+            Action::Toggle(id) => {
+                self.input_buffer.clear();
+                match self.toggle_by_dir_id(id) {
+                    Ok(()) => self.clear_status(),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::Enter if self.input_mode == InputMode::Search => {
+                // 确认搜索，保留过滤结果
+                self.input_mode = InputMode::Normal;
+                Ok(true)
+            }
+            Action::Enter if self.input_mode == InputMode::Move => {
+                self.input_mode = InputMode::Normal;
+                let destination = std::mem::take(&mut self.move_target);
+                match self.move_at_cursor(&destination) {
+                    Ok(path) => self.set_info(format!("Moved to {}", path.display())),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::Enter if self.input_mode == InputMode::Jump => {
+                self.input_mode = InputMode::Normal;
+                let target = self
+                    .jump_matches(self.jump_selected + 1)
+                    .get(self.jump_selected)
+                    .map(|node| node.path().to_path_buf());
+                self.jump_query.clear();
+                self.jump_selected = 0;
+                match target {
+                    Some(path) => {
+                        self.jump_to(&path);
                         self.clear_status();
                     }
-                    Err(e) => self.set_error(e.to_string()),
+                    None => self.set_error("No matching path"),
                 }
                 Ok(true)
             }
@@ -202,7 +1132,7 @@ impl<'a> UiState<'a> {
                         Err(e) => self.set_error(e.to_string()),
                     }
                 } else {
-                    let index = match self.input_buffer.parse::<usize>() {
+                    let id = match self.input_buffer.parse::<usize>() {
                         Ok(i) => i,
                         Err(_) => {
                             self.set_error(format!("Invalid: {}", self.input_buffer));
@@ -210,11 +1140,8 @@ impl<'a> UiState<'a> {
                             return Ok(true);
                         }
                     };
-                    match self.toggle_by_index(index) {
-                        Ok(()) => {
-                            self.cursor = index.min(self.flatten_view().len().saturating_sub(1));
-                            self.clear_status();
-                        }
+                    match self.toggle_by_dir_id(id) {
+                        Ok(()) => self.clear_status(),
                         Err(e) => self.set_error(e.to_string()),
                     }
                 }
@@ -229,20 +1156,663 @@ impl<'a> UiState<'a> {
                 Ok(true)
             }
             Action::InputBackspace => {
-                self.input_buffer.pop();
+                match self.input_mode {
+                    // 没有待输入的索引时，退格跳到父目录
+                    InputMode::Normal if self.input_buffer.is_empty() => {
+                        if let Some(parent) = self.parent_of_cursor() {
+                            self.cursor = parent;
+                        }
+                    }
+                    InputMode::Normal | InputMode::Confirm => {
+                        self.input_buffer.pop();
+                    }
+                    InputMode::Search => {
+                        self.search_query.pop();
+                        self.clamp_cursor();
+                    }
+                    InputMode::Jump => {
+                        self.jump_query.pop();
+                        self.jump_selected = 0;
+                    }
+                    InputMode::Move => {
+                        self.move_target.pop();
+                    }
+                }
+                self.clear_status();
+                Ok(true)
+            }
+            Action::StartSearch => {
+                self.input_buffer.clear();
+                self.clear_status();
+                self.input_mode = InputMode::Search;
+                Ok(true)
+            }
+            Action::StartJump => {
+                self.input_buffer.clear();
+                self.clear_status();
+                self.input_mode = InputMode::Jump;
+                self.jump_query.clear();
+                self.jump_selected = 0;
+                Ok(true)
+            }
+            Action::InputChar(ch) => {
+                match self.input_mode {
+                    InputMode::Search => {
+                        self.search_query.push(ch);
+                        self.clamp_cursor();
+                    }
+                    InputMode::Jump => {
+                        self.jump_query.push(ch);
+                        self.jump_selected = 0;
+                    }
+                    InputMode::Move => self.move_target.push(ch),
+                    InputMode::Normal | InputMode::Confirm => {}
+                }
+                Ok(true)
+            }
+            Action::ClearSearch if self.input_mode == InputMode::Move => {
+                // 取消移动
+                self.input_mode = InputMode::Normal;
+                self.move_target.clear();
                 self.clear_status();
                 Ok(true)
             }
+            Action::ClearSearch if self.input_mode == InputMode::Jump => {
+                // 取消跳转，不影响搜索过滤
+                self.input_mode = InputMode::Normal;
+                self.jump_query.clear();
+                self.jump_selected = 0;
+                Ok(true)
+            }
+            Action::ClearSearch => {
+                self.input_mode = InputMode::Normal;
+                self.search_query.clear();
+                self.clear_status();
+                self.clamp_cursor();
+                Ok(true)
+            }
             Action::ToggleSort => {
                 self.input_buffer.clear();
                 self.clear_status();
-                self.sort_mode = match self.sort_mode {
-                    SortMode::NameAsc => SortMode::SizeDesc,
-                    SortMode::SizeDesc => SortMode::NameAsc,
+                self.sort_mode = next_sort(self.sort_mode);
+                Ok(true)
+            }
+            Action::SortDirectory => {
+                self.input_buffer.clear();
+                match self.sort_directory_at_cursor() {
+                    Ok(path) => self.set_info(format!(
+                        "Sorted {} by {}",
+                        path.display(),
+                        sort_label(self.sort_mode)
+                    )),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::ToggleFileCount => {
+                self.input_buffer.clear();
+                self.clear_status();
+                self.column = if self.column == DisplayColumn::FileCount {
+                    DisplayColumn::Size
+                } else {
+                    DisplayColumn::FileCount
+                };
+                Ok(true)
+            }
+            Action::ToggleRelative => {
+                self.input_buffer.clear();
+                self.clear_status();
+                self.column = match self.column {
+                    DisplayColumn::Size => DisplayColumn::ParentPercent,
+                    DisplayColumn::ParentPercent => DisplayColumn::Percent,
+                    _ => DisplayColumn::Size,
                 };
                 Ok(true)
             }
+            Action::CycleColumn => {
+                self.input_buffer.clear();
+                self.clear_status();
+                self.column = self.column.next();
+                Ok(true)
+            }
+            Action::Rescan => {
+                self.input_buffer.clear();
+                match self.rescan_at_cursor() {
+                    Ok(path) => self.set_info(format!("Rescanned {}", path.display())),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                self.clamp_cursor();
+                Ok(true)
+            }
+            Action::Open => {
+                self.input_buffer.clear();
+                let result = self.node_at_cursor().and_then(|node| {
+                    if node.is_multi_root() {
+                        bail!("Cannot open the combined root");
+                    }
+                    open_in_file_manager(node.path())
+                });
+                match result {
+                    Ok(path) => self.set_info(format!("Opened {}", path.display())),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::Delete => {
+                self.input_buffer.clear();
+                match self.node_at_cursor() {
+                    Ok(node) if std::ptr::eq(node, &*self.root) => {
+                        self.set_error("Cannot delete the root directory")
+                    }
+                    Ok(node) if matches!(node.kind(), Omitted(_)) => {
+                        self.set_error("Cannot delete omitted entries")
+                    }
+                    Ok(node) => {
+                        let path = node.path().to_path_buf();
+                        let freed = format_size(node.reclaimable_size(), self.size_format);
+                        self.set_info(format!("Delete {} (frees {freed})? [y/N]", path.display()));
+                        self.pending_delete = vec![path];
+                        self.input_mode = InputMode::Confirm;
+                    }
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::ToggleMark => {
+                self.input_buffer.clear();
+                match self.toggle_mark_at_cursor() {
+                    Ok(()) => self.clear_status(),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::DeleteMarked => {
+                self.input_buffer.clear();
+                let (paths, total) = self.marked_roots();
+                if paths.is_empty() {
+                    self.set_error("Nothing marked (use Space to mark)");
+                    return Ok(true);
+                }
+                let noun = if paths.len() == 1 { "item" } else { "items" };
+                let freed: u64 = paths
+                    .iter()
+                    .filter_map(|path| self.root.find(path))
+                    .map(Node::reclaimable_size)
+                    .sum();
+                self.set_info(format!(
+                    "Delete {} marked {noun} ({}, frees {})? [y/N]",
+                    paths.len(),
+                    format_size(total, self.size_format),
+                    format_size(freed, self.size_format)
+                ));
+                self.pending_delete = paths;
+                self.input_mode = InputMode::Confirm;
+                Ok(true)
+            }
+            Action::Trash => {
+                self.input_buffer.clear();
+                match self.trash_at_cursor() {
+                    Ok(path) => self.set_info(format!("Moved {} to trash", path.display())),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                self.clamp_cursor();
+                Ok(true)
+            }
+            Action::Yank => {
+                self.input_buffer.clear();
+                match self.yank_at_cursor() {
+                    Ok(message) => self.set_info(message),
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::Confirm(confirmed) => {
+                self.input_mode = InputMode::Normal;
+                let paths = std::mem::take(&mut self.pending_delete);
+                if paths.is_empty() {
+                    return Ok(true);
+                }
+                if !confirmed {
+                    self.set_info("Delete cancelled");
+                    return Ok(true);
+                }
+                // 逐个删除，遇到错误即停止；已删除的节点不会恢复
+                let count = paths.len();
+                let mut deleted = 0;
+                let mut failure = None;
+                for path in &paths {
+                    if let Err(e) = self.delete(path) {
+                        failure = Some(format!("Cannot delete {}: {e}", path.display()));
+                        break;
+                    }
+                    deleted += 1;
+                }
+                match failure {
+                    Some(text) if count > 1 => {
+                        self.set_error(format!("{text} ({deleted} of {count} deleted)"))
+                    }
+                    Some(text) => self.set_error(text),
+                    None if count == 1 => self.set_info(format!("Deleted {}", paths[0].display())),
+                    None => self.set_info(format!("Deleted {count} items")),
+                }
+                self.clamp_cursor();
+                Ok(true)
+            }
+            Action::ToggleHidden => {
+                self.input_buffer.clear();
+                self.show_hidden = !self.show_hidden;
+                self.set_info(if self.show_hidden {
+                    "Showing hidden entries"
+                } else {
+                    "Hiding hidden entries"
+                });
+                self.clamp_cursor();
+                Ok(true)
+            }
+            Action::StartMove => {
+                self.input_buffer.clear();
+                match self.node_at_cursor() {
+                    Ok(node) => {
+                        // 以所在目录的绝对路径作为初始目标，便于编辑；相对路径按当前工作目录解析
+                        self.move_target = node
+                            .path()
+                            .parent()
+                            .map(|p| normalize_path(p).display().to_string())
+                            .unwrap_or_default();
+                        self.input_mode = InputMode::Move;
+                        self.clear_status();
+                    }
+                    Err(e) => self.set_error(e.to_string()),
+                }
+                Ok(true)
+            }
+            Action::ToggleFocus => {
+                self.input_buffer.clear();
+                if let Err(e) = self.toggle_focus() {
+                    self.set_error(e.to_string());
+                }
+                Ok(true)
+            }
+            Action::ToggleBreakdown => {
+                self.input_buffer.clear();
+                self.show_breakdown = !self.show_breakdown;
+                self.clear_status();
+                Ok(true)
+            }
+            Action::ToggleDirsOnly => {
+                self.input_buffer.clear();
+                self.dirs_only = !self.dirs_only;
+                self.set_info(if self.dirs_only {
+                    "Showing directories only"
+                } else {
+                    "Showing all entries"
+                });
+                self.clamp_cursor();
+                Ok(true)
+            }
+            Action::ToggleMinSize => {
+                self.input_buffer.clear();
+                if self.min_size == 0 {
+                    self.set_error("No minimum size set (use --min-size)");
+                } else {
+                    self.hide_small = !self.hide_small;
+                    self.clear_status();
+                    self.clamp_cursor();
+                }
+                Ok(true)
+            }
             Action::Quit => Ok(false),
         }
     }
 }
+
+/// 在系统文件管理器中打开路径：目录直接打开，其余条目打开所在目录，返回实际打开的路径
+fn open_in_file_manager(path: &Path) -> anyhow::Result<PathBuf> {
+    let target = if path.is_dir() {
+        path
+    } else {
+        path.parent()
+            .filter(|parent| !parent.as_os_str().is_empty())
+            .unwrap_or(Path::new("."))
+    };
+    let program = if cfg!(target_os = "macos") {
+        "open"
+    } else if cfg!(windows) {
+        "explorer"
+    } else {
+        "xdg-open"
+    };
+    let mut child = Command::new(program)
+        .arg(target)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|e| anyhow::anyhow!("Cannot launch {program}: {e}"))?;
+    // 在后台回收子进程，不阻塞界面
+    std::thread::spawn(move || child.wait());
+    Ok(target.to_path_buf())
+}
+
+/// 转换为绝对路径（相对路径按当前工作目录解析），并按字面消去 `.` 与 `..`，不访问文件系统
+fn normalize_path(path: &Path) -> PathBuf {
+    let absolute = std::path::absolute(path).unwrap_or_else(|_| path.to_path_buf());
+    let mut normalized = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            std::path::Component::CurDir => {}
+            std::path::Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
+
+/// 从磁盘删除路径：目录连同其内容一并删除，符号链接只删除链接本身
+fn remove_path(path: &Path) -> std::io::Result<()> {
+    if std::fs::symlink_metadata(path)?.is_dir() {
+        std::fs::remove_dir_all(path)
+    } else {
+        std::fs::remove_file(path)
+    }
+}
+
+/// 移动路径：先尝试重命名，跨文件系统时复制后删除原条目
+///
+/// 复制失败时删除已复制的部分，原条目保持不变。
+fn move_path(from: &Path, to: &Path) -> std::io::Result<()> {
+    match std::fs::rename(from, to) {
+        Err(e) if e.kind() == std::io::ErrorKind::CrossesDevices => {
+            if let Err(e) = copy_recursive(from, to) {
+                let _ = remove_path(to);
+                return Err(e);
+            }
+            remove_path(from)
+        }
+        result => result,
+    }
+}
+
+/// 递归复制文件、目录与符号链接（不跟随），目录保留权限
+fn copy_recursive(from: &Path, to: &Path) -> std::io::Result<()> {
+    let meta = std::fs::symlink_metadata(from)?;
+    if meta.is_dir() {
+        std::fs::create_dir(to)?;
+        for entry in std::fs::read_dir(from)? {
+            let entry = entry?;
+            copy_recursive(&entry.path(), &to.join(entry.file_name()))?;
+        }
+        std::fs::set_permissions(to, meta.permissions())
+    } else if meta.is_symlink() {
+        copy_symlink(from, to)
+    } else {
+        std::fs::copy(from, to).map(|_| ())
+    }
+}
+
+#[cfg(unix)]
+fn copy_symlink(from: &Path, to: &Path) -> std::io::Result<()> {
+    std::os::unix::fs::symlink(std::fs::read_link(from)?, to)
+}
+
+#[cfg(not(unix))]
+fn copy_symlink(from: &Path, _to: &Path) -> std::io::Result<()> {
+    Err(std::io::Error::new(
+        std::io::ErrorKind::Unsupported,
+        format!("cannot copy symbolic link {}", from.display()),
+    ))
+}
+
+/// 将路径移到系统回收站
+#[cfg(feature = "trash")]
+fn move_to_trash(path: &Path) -> anyhow::Result<()> {
+    trash::delete(path).map_err(|e| anyhow::anyhow!("Cannot move {} to trash: {e}", path.display()))
+}
+
+#[cfg(not(feature = "trash"))]
+fn move_to_trash(_path: &Path) -> anyhow::Result<()> {
+    bail!("Trash is not supported in this build (enable the `trash` feature)")
+}
+
+/// 将文本复制到系统剪贴板
+#[cfg(feature = "clipboard")]
+fn copy_to_clipboard(text: &str) -> anyhow::Result<()> {
+    arboard::Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| anyhow::anyhow!("Clipboard unavailable ({e})"))
+}
+
+#[cfg(not(feature = "clipboard"))]
+fn copy_to_clipboard(_text: &str) -> anyhow::Result<()> {
+    bail!("Clipboard is not supported in this build (enable the `clipboard` feature)")
+}
+
+/// 模糊匹配得分：`query`（已转为小写）的字符须按顺序出现在 `candidate` 中（不区分大小写），否则为 `None`
+///
+/// 分别从前向后、从后向前贪心匹配，取得分较高者；从后向前匹配更容易命中文件名。连续匹配、
+/// 位于路径分段或单词开头（`/`、`_`、`-`、`.`、空格之后）的匹配与位于文件名中的匹配加分，
+/// 匹配之间跳过的字符扣分。
+fn fuzzy_score(query: &[char], candidate: &str) -> Option<i64> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let name_start = chars.iter().rposition(|&ch| ch == '/').map_or(0, |i| i + 1);
+    let matches = |index: usize, wanted: char| chars[index].to_lowercase().eq([wanted]);
+
+    let mut forward = Vec::with_capacity(query.len());
+    let mut pending = query.iter().peekable();
+    for index in 0..chars.len() {
+        if let Some(&&wanted) = pending.peek()
+            && matches(index, wanted)
+        {
+            forward.push(index);
+            pending.next();
+        }
+    }
+    if forward.len() < query.len() {
+        return None;
+    }
+    let mut backward = Vec::with_capacity(query.len());
+    let mut pending = query.iter().rev().peekable();
+    for index in (0..chars.len()).rev() {
+        if let Some(&&wanted) = pending.peek()
+            && matches(index, wanted)
+        {
+            backward.push(index);
+            pending.next();
+        }
+    }
+    backward.reverse();
+
+    let score = |positions: &[usize]| {
+        let mut score = 0;
+        for (i, &index) in positions.iter().enumerate() {
+            score += 16;
+            if i > 0 {
+                let gap = index - positions[i - 1] - 1;
+                score += if gap == 0 { 24 } else { -(gap.min(16) as i64) };
+            }
+            if index == 0 || matches!(chars[index - 1], '/' | '_' | '-' | '.' | ' ') {
+                score += 20;
+            }
+            if index >= name_start {
+                score += 8;
+            }
+        }
+        score
+    };
+    Some(score(&forward).max(score(&backward)))
+}
+
+/// 排序方式的循环顺序：大小降序 → 大小升序 → 名称
+fn next_sort(order: SortOrder) -> SortOrder {
+    match order {
+        SortOrder::SizeDesc => SortOrder::SizeAsc,
+        SortOrder::SizeAsc => SortOrder::NameAsc,
+        SortOrder::NameAsc => SortOrder::SizeDesc,
+    }
+}
+
+/// 排序方式在界面中显示的名称
+pub fn sort_label(order: SortOrder) -> &'static str {
+    match order {
+        SortOrder::NameAsc => "name",
+        SortOrder::SizeDesc => "size",
+        SortOrder::SizeAsc => "size asc",
+    }
+}
+
+/// 展开深度不超过 `max_depth` 的目录
+fn expand_recursive_bounded(
+    expanded_nodes: &mut HashSet<PathBuf>,
+    node: &Node,
+    depth: usize,
+    max_depth: usize,
+) {
+    if depth > max_depth {
+        return;
+    }
+    if let Directory(prop) = node.kind()
+        && !prop.is_truncated()
+    {
+        expanded_nodes.insert(node.path().to_path_buf());
+        for child in prop.children() {
+            expand_recursive_bounded(expanded_nodes, child, depth + 1, max_depth);
+        }
+    }
+}
+
+/// 递归设置子树中所有目录的展开状态（未保留子节点的目录除外）
+fn set_expanded_recursive(expanded_nodes: &mut HashSet<PathBuf>, node: &Node, expanded: bool) {
+    if let Directory(prop) = node.kind()
+        && !prop.is_truncated()
+    {
+        if expanded {
+            expanded_nodes.insert(node.path().to_path_buf());
+        } else {
+            expanded_nodes.remove(node.path());
+        }
+        for child in prop.children() {
+            set_expanded_recursive(expanded_nodes, child, expanded);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::vfs::MemoryFs;
+    use std::fs;
+
+    /// 系统临时目录下的测试目录，以相对于当前工作目录的路径表示；离开作用域时删除
+    struct RelativeDir(PathBuf);
+
+    impl RelativeDir {
+        fn new(name: &str) -> Self {
+            let absolute =
+                std::env::temp_dir().join(format!("fswhy-test-{name}-{}", std::process::id()));
+            fs::create_dir(&absolute).unwrap();
+            // 先用 `..` 回到文件系统根目录，再进入临时目录
+            let cwd = std::env::current_dir().unwrap();
+            let mut path: PathBuf = cwd
+                .components()
+                .filter(|c| matches!(c, std::path::Component::Normal(_)))
+                .map(|_| std::path::Component::ParentDir)
+                .collect();
+            path.extend(
+                absolute
+                    .components()
+                    .filter(|c| matches!(c, std::path::Component::Normal(_))),
+            );
+            assert!(path.is_relative());
+            Self(path)
+        }
+    }
+
+    impl Drop for RelativeDir {
+        fn drop(&mut self) {
+            let _ = fs::remove_dir_all(&self.0);
+        }
+    }
+
+    /// 可见列表中各项的名称
+    fn view_names(state: &UiState) -> Vec<String> {
+        state
+            .flatten_view()
+            .iter()
+            .map(|item| display_name(item.node.path()).into_owned())
+            .collect()
+    }
+
+    #[test]
+    fn update_drives_navigation_and_sort() {
+        let fs = MemoryFs::new()
+            .file("/r/a/f", 5)
+            .file("/r/b/g", 50)
+            .file("/r/b/h", 1);
+        let mut root = Node::scan_with_fs(
+            PathBuf::from("/r"),
+            ScanOptions::default(),
+            &fs,
+            &mut |_| {},
+        )
+        .unwrap()
+        .root;
+        let mut state = UiState::new(&mut root, Theme::default());
+        assert_eq!(view_names(&state), ["r", "b", "a"]);
+
+        state.update(Action::MoveDown).unwrap();
+        assert_eq!(state.cursor, 1);
+
+        state.update(Action::Expand).unwrap();
+        assert!(state.expanded_nodes.contains(Path::new("/r/b")));
+        assert_eq!(view_names(&state), ["r", "b", "g", "h", "a"]);
+
+        state.update(Action::Collapse).unwrap();
+        assert!(!state.expanded_nodes.contains(Path::new("/r/b")));
+        assert_eq!(view_names(&state), ["r", "b", "a"]);
+
+        state.update(Action::ToggleSort).unwrap();
+        assert_eq!(state.sort_mode, SortOrder::SizeAsc);
+        assert_eq!(view_names(&state), ["r", "a", "b"]);
+    }
+
+    #[test]
+    fn move_under_relative_root() {
+        let dir = RelativeDir::new("move");
+        let root = &dir.0;
+        fs::create_dir_all(root.join("a")).unwrap();
+        fs::create_dir_all(root.join("b")).unwrap();
+        fs::write(root.join("a").join("file"), [0u8; 100]).unwrap();
+
+        let mut node = Node::scan(root.clone()).unwrap();
+        let mut state = UiState::new(&mut node, Theme::default());
+        let file = root.join("a").join("file");
+        state.jump_to(&file);
+
+        // 初始目标为所在目录的绝对路径，而不是相对于扫描根目录的写法
+        state.update(Action::StartMove).unwrap();
+        assert_eq!(
+            PathBuf::from(&state.move_target),
+            normalize_path(&root.join("a"))
+        );
+
+        // 相对路径按当前工作目录解析
+        state.move_target = root.join("b").display().to_string();
+        state.update(Action::Enter).unwrap();
+        let moved = root.join("b").join("file");
+        assert!(moved.is_file());
+        assert_eq!(state.root.find(&root.join("b")).unwrap().size(), 100);
+        assert_eq!(state.root.find(&root.join("a")).unwrap().size(), 0);
+
+        // 含 `..` 的绝对路径同样能在树中找到目标目录
+        state.jump_to(&moved);
+        state.update(Action::StartMove).unwrap();
+        let back = normalize_path(root).join("b").join("..").join("a");
+        state.move_target = back.display().to_string();
+        state.update(Action::Enter).unwrap();
+        assert!(file.is_file());
+        assert_eq!(state.root.find(&root.join("a")).unwrap().size(), 100);
+        assert_eq!(state.root.size(), 100);
+    }
+}
@@ -0,0 +1,399 @@
+//! 文件系统访问抽象
+//!
+//! 扫描通过 [`FsProvider`] 读取元数据与目录内容：[`StdFs`] 访问实际文件系统，
+//! [`MemoryFs`] 由内存中构造的虚拟树提供，无需创建临时目录即可得到确定的扫描结果。
+//! `.gitignore` 文件总是从实际文件系统读取。
+//!
+//! 在 Windows 上 [`StdFs`] 访问文件系统时为路径加上扩展长度前缀（`\\?\`，UNC 路径为 `\\?\UNC\`），
+//! 使超过 260 个字符的深层路径（如 `node_modules`）也能访问；前缀只用于系统调用，返回的条目路径不包含前缀。
+
+use std::borrow::Cow;
+use std::collections::{BTreeMap, BTreeSet};
+use std::io;
+use std::path::{Component, Path, PathBuf};
+use std::time::SystemTime;
+
+/// 条目类型（不跟随符号链接时为链接本身）
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum FsFileType {
+    File,
+    Dir,
+    Symlink,
+}
+
+/// 扫描所需的元数据
+#[derive(Clone, Debug, PartialEq)]
+pub struct FsMetadata {
+    pub file_type: FsFileType,
+    /// 表观大小（字节）
+    pub len: u64,
+    /// 实际分配的磁盘空间（字节），未知时为 `None`，此时按表观大小统计
+    pub allocated: Option<u64>,
+    pub modified: Option<SystemTime>,
+    /// 设备号与 inode 号，平台不支持时为 `None`
+    pub inode: Option<(u64, u64)>,
+    /// 指向该 inode 的链接数
+    pub nlink: u64,
+    /// 所有者的用户 ID 与组 ID，平台不支持时为 `None`
+    pub owner: Option<(u32, u32)>,
+    /// 权限位（含文件类型位），平台不支持时为 `None`
+    pub mode: Option<u32>,
+}
+
+impl FsMetadata {
+    pub fn is_dir(&self) -> bool {
+        self.file_type == FsFileType::Dir
+    }
+
+    pub fn is_symlink(&self) -> bool {
+        self.file_type == FsFileType::Symlink
+    }
+}
+
+impl From<std::fs::Metadata> for FsMetadata {
+    fn from(meta: std::fs::Metadata) -> Self {
+        let file_type = if meta.file_type().is_symlink() {
+            FsFileType::Symlink
+        } else if meta.is_dir() {
+            FsFileType::Dir
+        } else {
+            FsFileType::File
+        };
+        #[cfg(unix)]
+        let (allocated, inode, nlink, owner, mode) = {
+            use std::os::unix::fs::MetadataExt;
+            (
+                Some(meta.blocks() * 512),
+                Some((meta.dev(), meta.ino())),
+                meta.nlink(),
+                Some((meta.uid(), meta.gid())),
+                Some(meta.mode()),
+            )
+        };
+        #[cfg(not(unix))]
+        let (allocated, inode, nlink, owner, mode) = (None, None, 1, None, None);
+        Self {
+            file_type,
+            len: meta.len(),
+            allocated,
+            modified: meta.modified().ok(),
+            inode,
+            nlink,
+            owner,
+            mode,
+        }
+    }
+}
+
+/// 目录中的一个条目
+#[derive(Clone, Debug, PartialEq)]
+pub struct FsEntry {
+    pub path: PathBuf,
+    /// 不跟随符号链接的类型，无法获取时为 `None`
+    pub file_type: Option<FsFileType>,
+}
+
+/// 扫描使用的文件系统访问接口，可能被多个扫描线程同时调用
+pub trait FsProvider: Sync {
+    /// 条目自身的元数据，不跟随符号链接
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// 跟随符号链接后的元数据
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata>;
+
+    /// 目录的直接子条目；目录本身无法读取时返回错误，单个条目无法读取时对应项为错误
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<io::Result<FsEntry>>>;
+
+    /// 扩展属性（xattr）占用的字节数：各属性名称与值的长度之和，跟随符号链接
+    ///
+    /// 默认实现没有扩展属性，返回 0。
+    fn xattr_size(&self, _path: &Path) -> io::Result<u64> {
+        Ok(0)
+    }
+}
+
+/// 实际文件系统，委托给 [`std::fs`]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct StdFs;
+
+impl FsProvider for StdFs {
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        std::fs::symlink_metadata(extended_length(path)).map(FsMetadata::from)
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        std::fs::metadata(extended_length(path)).map(FsMetadata::from)
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<io::Result<FsEntry>>> {
+        Ok(std::fs::read_dir(extended_length(path))?
+            .map(|entry| {
+                entry.map(|entry| FsEntry {
+                    file_type: entry.file_type().ok().map(|t| {
+                        if t.is_symlink() {
+                            FsFileType::Symlink
+                        } else if t.is_dir() {
+                            FsFileType::Dir
+                        } else {
+                            FsFileType::File
+                        }
+                    }),
+                    // 由传入的路径拼接，不带扩展长度前缀
+                    path: path.join(entry.file_name()),
+                })
+            })
+            .collect())
+    }
+
+    /// 需要启用 `xattr` 特性，且只在支持扩展属性的平台（Linux、macOS 等）上有效，否则返回 0
+    fn xattr_size(&self, path: &Path) -> io::Result<u64> {
+        read_xattr_size(&extended_length(path))
+    }
+}
+
+/// 读取路径所有扩展属性的名称与值，返回长度之和
+#[cfg(feature = "xattr")]
+fn read_xattr_size(path: &Path) -> io::Result<u64> {
+    let mut size = 0;
+    for name in xattr::list_deref(path)? {
+        let value = xattr::get_deref(path, &name)?;
+        size += (name.len() + value.map_or(0, |v| v.len())) as u64;
+    }
+    Ok(size)
+}
+
+#[cfg(not(feature = "xattr"))]
+fn read_xattr_size(_path: &Path) -> io::Result<u64> {
+    Ok(0)
+}
+
+/// 转换为 Windows 扩展长度路径（`\\?\C:\...`、`\\?\UNC\server\share\...`），不受 260 个字符的限制
+///
+/// 相对路径先转为绝对路径并规范化（扩展长度路径中 `.`、`..` 与 `/` 不被解析）；已有前缀或无法转换的路径原样返回。
+#[cfg(windows)]
+fn extended_length(path: &Path) -> Cow<'_, Path> {
+    use std::ffi::OsString;
+    use std::path::Prefix;
+
+    let Ok(absolute) = std::path::absolute(path) else {
+        return Cow::Borrowed(path);
+    };
+    let mut components = absolute.components();
+    let Some(Component::Prefix(prefix)) = components.next() else {
+        return Cow::Borrowed(path);
+    };
+    let mut extended = match prefix.kind() {
+        Prefix::Disk(_) => {
+            let mut extended = OsString::from(r"\\?\");
+            extended.push(absolute.as_os_str());
+            return Cow::Owned(PathBuf::from(extended));
+        }
+        Prefix::UNC(server, share) => {
+            let mut extended = OsString::from(r"\\?\UNC\");
+            extended.push(server);
+            extended.push(r"\");
+            extended.push(share);
+            PathBuf::from(extended)
+        }
+        // 已是扩展长度路径或设备路径
+        _ => return Cow::Borrowed(path),
+    };
+    // 规范化后余下的是根目录与普通组成部分，只拼接后者
+    extended.extend(components.filter(|c| matches!(c, Component::Normal(_))));
+    Cow::Owned(extended)
+}
+
+/// 其他平台没有路径长度限制，原样返回
+#[cfg(not(windows))]
+fn extended_length(path: &Path) -> Cow<'_, Path> {
+    Cow::Borrowed(path)
+}
+
+/// [`MemoryFs`] 中的条目
+#[derive(Clone, Debug)]
+enum MemoryEntry {
+    Dir,
+    File {
+        len: u64,
+    },
+    Symlink {
+        target: PathBuf,
+    },
+    /// 访问时返回该类型的错误
+    Error(io::ErrorKind),
+}
+
+/// 内存中的虚拟文件树
+///
+/// 以构建器方式添加条目，如 `MemoryFs::new().file("/data/a.log", 100).symlink("/data/latest", "a.log")`。
+/// 添加条目时自动创建缺失的上层目录；目录按路径顺序列出子条目，每个条目有独立的 inode。
+/// 目录的表观大小为 [`MEMORY_DIR_LEN`]；条目默认位于设备 0，[`mount`](Self::mount) 添加的挂载点及其内容位于其他设备。
+#[derive(Clone, Debug, Default)]
+pub struct MemoryFs {
+    entries: BTreeMap<PathBuf, MemoryEntry>,
+    /// 挂载点，按路径顺序编号为设备 1、2……
+    mounts: BTreeSet<PathBuf>,
+}
+
+/// [`MemoryFs`] 中目录的表观大小（字节），与常见的 Linux 文件系统一致
+pub const MEMORY_DIR_LEN: u64 = 4096;
+
+/// 跟随符号链接的最大次数，超过视为循环
+const MAX_SYMLINK_HOPS: usize = 40;
+
+impl MemoryFs {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// 添加目录
+    pub fn dir(self, path: impl Into<PathBuf>) -> Self {
+        self.insert(path.into(), MemoryEntry::Dir)
+    }
+
+    /// 添加大小为 `len` 字节的文件
+    pub fn file(self, path: impl Into<PathBuf>, len: u64) -> Self {
+        self.insert(path.into(), MemoryEntry::File { len })
+    }
+
+    /// 添加指向 `target` 的符号链接；相对目标相对于链接所在目录
+    pub fn symlink(self, path: impl Into<PathBuf>, target: impl Into<PathBuf>) -> Self {
+        self.insert(
+            path.into(),
+            MemoryEntry::Symlink {
+                target: target.into(),
+            },
+        )
+    }
+
+    /// 添加位于另一文件系统上的目录（挂载点），其中的条目与挂载点位于同一设备
+    pub fn mount(mut self, path: impl Into<PathBuf>) -> Self {
+        let path = path.into();
+        self.mounts.insert(path.clone());
+        self.insert(path, MemoryEntry::Dir)
+    }
+
+    /// 添加一个无法访问的条目，读取其元数据时返回 `kind` 类型的错误
+    pub fn error(self, path: impl Into<PathBuf>, kind: io::ErrorKind) -> Self {
+        self.insert(path.into(), MemoryEntry::Error(kind))
+    }
+
+    fn insert(mut self, path: PathBuf, entry: MemoryEntry) -> Self {
+        for ancestor in path.ancestors().skip(1) {
+            if ancestor.as_os_str().is_empty() {
+                break;
+            }
+            self.entries
+                .entry(ancestor.to_path_buf())
+                .or_insert(MemoryEntry::Dir);
+        }
+        self.entries.insert(path, entry);
+        self
+    }
+
+    /// 解析路径中的符号链接与 `.`、`..`，返回条目在树中的实际路径；`follow_last` 决定是否跟随最后一级
+    fn resolve(&self, path: &Path, follow_last: bool) -> io::Result<PathBuf> {
+        let mut resolved = PathBuf::new();
+        let mut remaining: Vec<Component> = path.components().rev().collect();
+        let mut hops = 0;
+        while let Some(component) = remaining.pop() {
+            match component {
+                Component::CurDir => {}
+                Component::ParentDir => {
+                    resolved.pop();
+                }
+                other => {
+                    resolved.push(other);
+                    if remaining.is_empty() && !follow_last {
+                        break;
+                    }
+                    if let MemoryEntry::Symlink { target } = self.lookup(&resolved)? {
+                        hops += 1;
+                        if hops > MAX_SYMLINK_HOPS {
+                            return Err(io::Error::other("too many levels of symbolic links"));
+                        }
+                        // 相对目标相对于链接所在目录，绝对目标替换已解析的部分
+                        resolved.pop();
+                        remaining.extend(target.components().rev());
+                    }
+                }
+            }
+        }
+        Ok(resolved)
+    }
+
+    fn lookup(&self, path: &Path) -> io::Result<&MemoryEntry> {
+        match self.entries.get(path) {
+            Some(MemoryEntry::Error(kind)) => Err(io::Error::from(*kind)),
+            Some(entry) => Ok(entry),
+            None => Err(io::Error::from(io::ErrorKind::NotFound)),
+        }
+    }
+
+    fn metadata_of(&self, path: &Path, entry: &MemoryEntry) -> FsMetadata {
+        let (file_type, len) = match entry {
+            MemoryEntry::Dir => (FsFileType::Dir, MEMORY_DIR_LEN),
+            MemoryEntry::Error(_) => (FsFileType::Dir, 0),
+            MemoryEntry::File { len } => (FsFileType::File, *len),
+            MemoryEntry::Symlink { target } => {
+                (FsFileType::Symlink, target.as_os_str().len() as u64)
+            }
+        };
+        let ino = self.entries.keys().position(|p| p == path).unwrap_or(0) as u64 + 1;
+        // 最近的上层挂载点决定设备，不在任何挂载点之下时为 0
+        let device = self
+            .mounts
+            .iter()
+            .enumerate()
+            .filter(|(_, mount)| path.starts_with(mount))
+            .max_by_key(|(_, mount)| mount.components().count())
+            .map_or(0, |(index, _)| index as u64 + 1);
+        FsMetadata {
+            file_type,
+            len,
+            allocated: None,
+            modified: None,
+            inode: Some((device, ino)),
+            nlink: 1,
+            owner: None,
+            mode: None,
+        }
+    }
+}
+
+impl FsProvider for MemoryFs {
+    fn symlink_metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let real = self.resolve(path, false)?;
+        Ok(self.metadata_of(&real, self.lookup(&real)?))
+    }
+
+    fn metadata(&self, path: &Path) -> io::Result<FsMetadata> {
+        let real = self.resolve(path, true)?;
+        Ok(self.metadata_of(&real, self.lookup(&real)?))
+    }
+
+    fn read_dir(&self, path: &Path) -> io::Result<Vec<io::Result<FsEntry>>> {
+        let real = self.resolve(path, true)?;
+        match self.lookup(&real)? {
+            MemoryEntry::Dir => {}
+            _ => return Err(io::Error::from(io::ErrorKind::NotADirectory)),
+        }
+        // 与 std::fs::read_dir 一致，条目路径位于传入的路径之下
+        Ok(self
+            .entries
+            .iter()
+            .filter(|(child, _)| child.parent() == Some(real.as_path()))
+            .map(|(child, entry)| {
+                Ok(FsEntry {
+                    path: path.join(child.file_name().unwrap_or_default()),
+                    file_type: match entry {
+                        MemoryEntry::Dir => Some(FsFileType::Dir),
+                        MemoryEntry::File { .. } => Some(FsFileType::File),
+                        MemoryEntry::Symlink { .. } => Some(FsFileType::Symlink),
+                        MemoryEntry::Error(_) => None,
+                    },
+                })
+            })
+            .collect())
+    }
+}